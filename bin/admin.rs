@@ -0,0 +1,183 @@
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use clap::{Parser, Subcommand};
+use mongodb::bson::doc;
+use mongodb::options::FindOptions;
+use rand::RngCore;
+
+use actix_mongo_jwt_web_template::{
+	auth::{create_user, RegisterError, validate_password},
+	auth::middleware::create_token,
+	auth::revocation::revoke_all,
+	config::{self, AppConfig},
+	manager::{connect_database, DatabaseWrapper, init_database},
+	manager::migration::{migrate, rollback, status},
+	repository::Repository,
+	schema::User,
+};
+
+/// user management and ops tasks, use same configuration as server
+#[derive(Parser)]
+#[command(name = "admin")]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// create user, password is read from stdin unless `--password` is given
+	CreateUser {
+		username: String,
+		#[arg(long)]
+		password: Option<String>,
+		/// role of user, can be repeated (e.g. `--role admin`)
+		#[arg(long = "role")]
+		roles: Vec<String>,
+	},
+	/// change password of user and revoke every token of user
+	SetPassword {
+		username: String,
+		#[arg(long)]
+		password: Option<String>,
+	},
+	/// delete user and revoke every token of user
+	DeleteUser {
+		username: String,
+	},
+	/// list every user
+	ListUsers,
+	/// print jwt token of user
+	IssueToken {
+		username: String,
+	},
+	/// apply, revert or list database migration
+	Migrate {
+		#[command(subcommand)]
+		command: Option<MigrateCommand>,
+	},
+	/// print random secret usable as `AUTH_JWT_SECRET`
+	GenSecret {
+		/// length of secret before encoding
+		#[arg(long, default_value_t = 64)]
+		bytes: usize,
+	},
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+	/// apply pending migration (default)
+	Up,
+	/// revert every migration newer than `version`
+	Down {
+		version: i64,
+	},
+	/// list migration
+	Status,
+}
+
+#[actix_rt::main]
+async fn main() -> Result<()> {
+	dotenv::dotenv().ok();
+	tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+	let cli = Cli::parse();
+
+	// doesn't need configuration
+	if let Command::GenSecret { bytes } = cli.command {
+		let mut secret = vec![0u8; bytes];
+		rand::thread_rng().fill_bytes(&mut secret);
+		println!("{}", URL_SAFE_NO_PAD.encode(secret));
+		return Ok(());
+	}
+
+	let config = config::init(AppConfig::load()?);
+	if let Command::Migrate { command } = cli.command {
+		// index and pending migration are left to the command
+		let db = connect_database(&config.database).await?;
+		return migrate_command(&db, command.unwrap_or(MigrateCommand::Up)).await;
+	}
+
+	let db = init_database(&config.database).await?;
+	match cli.command {
+		Command::CreateUser { username, password, roles } => {
+			let password = read_password(password)?;
+			let user = create_user(&db, &username, &password, &roles).await.map_err(|err| match err {
+				RegisterError::Invalid(reason) => anyhow!("{}", reason),
+				RegisterError::UsernameTaken => anyhow!("username `{}` is already taken", username),
+				RegisterError::Internal(err) => err,
+			})?;
+			println!("created user {} ({})", user.username(), user.id_ref());
+		}
+		Command::SetPassword { username, password } => {
+			let mut user = find_user(&db, &username).await?;
+			let password = read_password(password)?;
+			validate_password(&password).map_err(|reason| anyhow!("{}", reason))?;
+			if !user.set_password(&password).await {
+				bail!("failed to hash password");
+			}
+			db.users().save(&user).await?;
+			revoke_all(&db, user.id_ref()).await?;
+			println!("changed password of {}", user.username());
+		}
+		Command::DeleteUser { username } => {
+			let user = find_user(&db, &username).await?;
+			db.users().delete_by_id(*user.id_ref()).await?;
+			revoke_all(&db, user.id_ref()).await?;
+			println!("deleted user {} ({})", user.username(), user.id_ref());
+		}
+		Command::ListUsers => {
+			let option = FindOptions::builder().sort(doc! {"username":1}).build();
+			for user in db.users().find_many(doc! {}, option).await? {
+				println!("{}\t{}\t{}", user.id_ref(), user.username(), user.roles().join(","));
+			}
+		}
+		Command::IssueToken { username } => {
+			let user = find_user(&db, &username).await?;
+			println!("{}", create_token(&db, &user).await?);
+		}
+		Command::Migrate { .. } | Command::GenSecret { .. } => unreachable!(),
+	}
+	Ok(())
+}
+
+async fn find_user(db: &DatabaseWrapper, username: &str) -> Result<User> {
+	db.users().find_by_username(username).await?
+		.with_context(|| format!("user `{}` not found", username))
+}
+
+/// use password from argument or read single line from stdin
+fn read_password(password: Option<String>) -> Result<String> {
+	if let Some(password) = password {
+		return Ok(password);
+	}
+	eprint!("password: ");
+	std::io::stderr().flush()?;
+	let mut line = String::new();
+	std::io::stdin().read_line(&mut line)?;
+	Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+async fn migrate_command(db: &DatabaseWrapper, command: MigrateCommand) -> Result<()> {
+	match command {
+		MigrateCommand::Up => {
+			let done = migrate(db).await?;
+			println!("applied {} migration(s) {:?}", done.len(), done);
+		}
+		MigrateCommand::Down { version } => {
+			let done = rollback(db, version).await?;
+			println!("reverted {} migration(s) {:?}", done.len(), done);
+		}
+		MigrateCommand::Status => {
+			for it in status(db).await? {
+				let applied_at = it.applied_at
+					.map(|time| time.try_to_rfc3339_string().unwrap_or_default())
+					.unwrap_or_else(|| "pending".to_string());
+				println!("{:>6} {:<32} {}", it.migration.version, it.migration.name, applied_at);
+			}
+		}
+	}
+	Ok(())
+}
@@ -1,36 +1,83 @@
-use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use actix_cors::Cors;
 use actix_web::{App, HttpResponse, HttpServer, web};
+use actix_web::dev::ServerHandle;
 use actix_web::http::KeepAlive;
-use actix_web::middleware::DefaultHeaders;
+use actix_web::middleware::{Condition, DefaultHeaders};
 use actix_web::web::Data;
 use anyhow::Result;
 
 use actix_mongo_jwt_web_template::{
-	controller::{AuthController, Controller},
-	manager::init_database,
-	util::{
-		bool_ext::BoolExt,
-		env::env,
-	},
-	web::error::ApiStatus,
+	auth::client_cert,
+	auth::middleware::{key_ring, reload_key_ring},
+	config::{self, AppConfig},
+	controller::{AuthController, Controller, HealthController, MetricsController, WellKnownController},
+	manager::connect_database,
+	manager::database::preload,
+	manager::shutdown::{begin_shutdown, is_shutting_down, on_shutdown, run_shutdown_hooks, Stage},
+	manager::tls::{CertResolver, watch_certificate},
+	web::error::ApiError,
+	web::extract,
+	web::problem::ProblemResponse,
+	util::logging::init_logging,
+	web::https_redirect::HttpsRedirect,
+	web::preload::RequirePreloaded,
+	web::metrics::RequestMetrics,
+	web::request_id::RequestTracing,
 };
 
 #[actix_rt::main]
 async fn main() -> Result<()> {
 	dotenv::dotenv().ok();
-	tracing_subscriber::fmt::init();
+	init_logging();
 
-	let database = init_database().await?;
+	// every invalid value is reported at once
+	let config = config::init(AppConfig::load()?);
 
+	if std::env::args().any(|arg| arg == "--print-config") {
+		println!("{}", config.to_redacted_toml());
+		return Ok(());
+	}
+
+	// load jwt key early so misconfiguration fail at startup
+	key_ring();
+	let cert_resolver = match &config.http.tls {
+		Some(tls) => Some(Arc::new(CertResolver::new(tls)?)),
+		None => None,
+	};
+	if let Some(resolver) = &cert_resolver {
+		actix_rt::spawn(watch_certificate(resolver.clone()));
+	}
+	#[cfg(unix)]
+	actix_rt::spawn(reload_on_hangup(cert_resolver.clone()));
+
+	let database = connect_database(&config.database).await?;
+	// server start before index / migration are done so liveness probe pass while migrating,
+	// `/health/ready` and routes using database respond 503 until it's done
+	let preload_database = database.clone();
+	let preload_config = config.clone();
+	actix_rt::spawn(async move {
+		if let Err(err) = preload(&preload_database, &preload_config.database).await {
+			// database connection is closed by shutdown hook
+			if is_shutting_down() {
+				return;
+			}
+			log::error!("failed to prepare database: {:?}", err);
+			std::process::exit(1);
+		}
+	});
+
+	let app_config = Data::from(config.clone());
+	let redirect_host = config.http.tls.as_ref().filter(|it| it.redirect).and_then(|it| it.redirect_host.clone());
 	let server = HttpServer::new(move || {
 		let mut cors = Cors::default()
 			.allow_any_header()
 			.allow_any_method()
 			.supports_credentials();
-		if let Some(hosts) = env("HTTP_CORS_HOSTS") {
-			for host in hosts.as_str().split(",") {
+		if let Some(hosts) = &app_config.http.cors_hosts {
+			for host in hosts {
 				cors = cors.allowed_origin(host);
 			}
 		} else {
@@ -38,44 +85,145 @@ async fn main() -> Result<()> {
 		}
 
 		let mut app: App<_> = App::new()
+			.wrap(ProblemResponse::new(app_config.http.error_format, app_config.http.error_type_base.as_deref()))
 			.wrap(DefaultHeaders::new()
 				.add(("X-Frame-Options", "DENY"))// deny loading in iframe
 				.add(("Referrer-Policy", "no-referrer")))
 			.wrap(cors)
-			.app_data(Data::new(database.clone()));
+			.wrap(RequestMetrics)
+			.wrap(RequestTracing)
+			.app_data(app_config.clone())
+			.app_data(Data::new(database.clone()))
+			// respond `ApiStatus` json when extractor fail instead of plain text
+			.app_data(extract::json_config(app_config.http.json_limit))
+			.app_data(extract::form_config())
+			.app_data(extract::payload_config())
+			.app_data(extract::query_config())
+			.app_data(extract::path_config());
 
-		app = app.service(AuthController::create_scope())
+		// probe and scraper aren't redirected, kubernetes treat 3xx as success
+		app = app.service(HealthController::create_service())
+		         .service(MetricsController::create_service())
+		         .service(web::scope("")
+			         .wrap(Condition::new(redirect_host.is_some(), HttpsRedirect::new(redirect_host.as_deref().unwrap_or("localhost"))))
+			         .service(WellKnownController::create_service())
+			         // controller using database, unique index must exist before anything is written
+			         .service(web::scope("")
+				         .wrap(RequirePreloaded)
+				         .service(AuthController::create_service())))
 		         .default_service(web::route().to(not_found));
 		app
+	})
+		// keep client certificate of mutual tls connection for `ClientCert` extractor
+		.on_connect(client_cert::on_connect);
+	let http = &config.http;
+	let mut server = server;
+	if let Some(socket) = &http.bind_socket {
+		server = server.bind_uds(socket)?;
+		// actix remove stale socket before binding but not after stopping
+		let socket = socket.clone();
+		on_shutdown(Stage::Application, "unix socket", move || async move {
+			if let Err(err) = std::fs::remove_file(&socket) {
+				log::warn!("failed to remove unix socket {}: {}", socket.display(), err);
+			}
+		});
+	}
+	// `HTTP_BIND` is required by config unless socket or https is used
+	if let (Some(bind), true) = (&http.bind, http.bind_socket.is_none() || http.bind_tcp_with_socket) {
+		server = server.bind(bind)?;
+	}
+	if let (Some(tls), Some(resolver)) = (&http.tls, &cert_resolver) {
+		server = server.bind_rustls_0_23(&tls.bind, resolver.server_config()?)?;
+	}
+
+	// spans are exported in batch, send what's left
+	#[cfg(feature = "otel")]
+	on_shutdown(Stage::Telemetry, "opentelemetry", || async {
+		let _ = actix_rt::task::spawn_blocking(actix_mongo_jwt_web_template::util::otel::shutdown).await;
 	});
-	let server = if let Some(socket) = env("HTTP_BIND_SOCKET") {
-		let mut sock = server.bind_uds(socket)?;
-		let may_bind_ip = env("HTTP_BIND_SOCKET_ONLY").may_true();
-		if may_bind_ip {
-			sock = sock.bind(env("HTTP_BIND").expect("please set `HTTP_BIND` in environment variable"))?
+
+	let server = server
+		.keep_alive(KeepAlive::Timeout(Duration::from_secs(http.keep_alive)))
+		.shutdown_timeout(http.shutdown_timeout)
+		// signal is handled by `stop_on_signal` to report not ready before listener is closed
+		.disable_signals()
+		.run();
+	actix_rt::spawn(stop_on_signal(server.handle(), Duration::from_secs(http.shutdown_delay)));
+	let result = server.await;
+	log::info!("server stopped");
+	run_shutdown_hooks().await;
+	Ok(result?)
+}
+
+/// stop server gracefully once SIGTERM or SIGINT is received
+/// + `/health/ready` report 503 right away
+/// + on SIGTERM server keep serving for `delay` so load balancer can notice, SIGINT (ctrl-c) doesn't wait
+/// + then listener is closed and in-flight request has `HTTP_SHUTDOWN_TIMEOUT` seconds to finish
+async fn stop_on_signal(server: ServerHandle, delay: Duration) {
+	let terminate = shutdown_signal().await;
+	begin_shutdown();
+	if terminate && !delay.is_zero() {
+		log::info!("SIGTERM received, stop accepting connection in {}s", delay.as_secs());
+		actix_rt::time::sleep(delay).await;
+	}
+	log::info!("stopping server");
+	server.stop(true).await;
+}
+
+/// wait until process is asked to stop, return true if it was SIGTERM
+#[cfg(unix)]
+async fn shutdown_signal() -> bool {
+	use actix_rt::signal::unix::{signal, SignalKind};
+	use futures::future::{Either, select};
+
+	let mut sigterm = match signal(SignalKind::terminate()) {
+		Ok(sigterm) => sigterm,
+		Err(err) => {
+			log::warn!("failed to listen SIGTERM, only ctrl-c stop server gracefully: {}", err);
+			let _ = actix_rt::signal::ctrl_c().await;
+			return false;
 		}
-		sock
-	} else {
-		server.bind(env("HTTP_BIND").expect("please set `HTTP_BIND` in environment variable"))?
 	};
-
-	server.keep_alive(
-		keep_alive().unwrap_or(KeepAlive::Timeout(std::time::Duration::from_secs(30)))
-	).run().await?;
-	Ok(())
+	let terminate = Box::pin(sigterm.recv());
+	let interrupt = Box::pin(actix_rt::signal::ctrl_c());
+	let result = select(terminate, interrupt).await;
+	matches!(result, Either::Left(_))
 }
 
-// not found handler this will response as json error
-async fn not_found() -> HttpResponse {
-	HttpResponse::NotFound().json(ApiStatus::error("Not Found".to_string()))
+/// wait until process is asked to stop (ctrl-c)
+#[cfg(not(unix))]
+async fn shutdown_signal() -> bool {
+	let _ = actix_rt::signal::ctrl_c().await;
+	false
 }
 
-/// helper function
-fn keep_alive() -> Option<KeepAlive> {
-	let alive = env("HTTP_KEEP_ALIVE")?;
-	if alive.is_empty() { return None; }
+/// reload jwt key ring and tls certificate every time process receive SIGHUP (`kill -HUP <pid>`)
+#[cfg(unix)]
+async fn reload_on_hangup(cert_resolver: Option<Arc<CertResolver>>) {
+	use actix_rt::signal::unix::{signal, SignalKind};
 
-	let secs = std::time::Duration::from_secs(u64::from_str(alive.as_str()).ok()?);
+	let mut hangup = match signal(SignalKind::hangup()) {
+		Ok(hangup) => hangup,
+		Err(err) => {
+			log::warn!("failed to listen SIGHUP, jwt key ring / tls certificate can't be reloaded: {}", err);
+			return;
+		}
+	};
+	while hangup.recv().await.is_some() {
+		match reload_key_ring() {
+			Ok(_) => log::info!("jwt key ring reloaded"),
+			Err(err) => log::error!("failed to reload jwt key ring: {:#}", err),
+		}
+		if let Some(resolver) = &cert_resolver {
+			match resolver.reload() {
+				Ok(_) => log::info!("tls certificate reloaded"),
+				Err(err) => log::error!("failed to reload tls certificate: {:#}", err),
+			}
+		}
+	}
+}
 
-	Some(KeepAlive::Timeout(secs))
+// not found handler this will response as json error
+async fn not_found() -> Result<HttpResponse, ApiError> {
+	Err(ApiError::NotFound)
 }
\ No newline at end of file
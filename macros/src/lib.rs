@@ -0,0 +1,199 @@
+//! derive macro used by actix mongodb jwt template
+
+#![warn(missing_docs)]
+#![forbid(unsafe_code)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, GenericArgument, Ident, LitBool, LitInt, LitStr, parse_macro_input, PathArguments, Result, Type};
+use syn::meta::ParseNestedMeta;
+
+/// index declared by `#[repository(index(..))]`
+struct Index {
+	field: LitStr,
+	unique: bool,
+	sparse: bool,
+	expire_after: Option<LitInt>,
+	name: Option<LitStr>,
+}
+
+/// every `#[repository(..)]` attribute of struct
+#[derive(Default)]
+struct Args {
+	collection: Option<LitStr>,
+	accessor: Option<LitStr>,
+	indexes: Vec<Index>,
+}
+
+impl Args {
+	fn parse(input: &DeriveInput) -> Result<Self> {
+		let mut args = Args::default();
+		for attr in input.attrs.iter().filter(|it| it.path().is_ident("repository")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("collection") {
+					args.collection = Some(meta.value()?.parse()?);
+				} else if meta.path.is_ident("accessor") {
+					args.accessor = Some(meta.value()?.parse()?);
+				} else if meta.path.is_ident("index") {
+					args.indexes.push(parse_index(&meta)?);
+				} else {
+					return Err(meta.error("expected `collection`, `accessor` or `index`"));
+				}
+				Ok(())
+			})?;
+		}
+		Ok(args)
+	}
+}
+
+fn parse_index(meta: &ParseNestedMeta) -> Result<Index> {
+	let mut field = None;
+	let mut index = Index { field: LitStr::new("", Span::call_site()), unique: false, sparse: false, expire_after: None, name: None };
+	meta.parse_nested_meta(|meta| {
+		if meta.path.is_ident("field") {
+			field = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("unique") {
+			index.unique = flag(&meta)?;
+		} else if meta.path.is_ident("sparse") {
+			index.sparse = flag(&meta)?;
+		} else if meta.path.is_ident("expire_after") {
+			index.expire_after = Some(meta.value()?.parse()?);
+		} else if meta.path.is_ident("name") {
+			index.name = Some(meta.value()?.parse()?);
+		} else {
+			return Err(meta.error("expected `field`, `unique`, `sparse`, `expire_after` or `name`"));
+		}
+		Ok(())
+	})?;
+	index.field = field.ok_or_else(|| meta.error("index require `field = \"..\"`"))?;
+	Ok(index)
+}
+
+/// `unique` or `unique = true`
+fn flag(meta: &ParseNestedMeta) -> Result<bool> {
+	if meta.input.peek(syn::Token![=]) {
+		Ok(meta.value()?.parse::<LitBool>()?.value)
+	} else {
+		Ok(true)
+	}
+}
+
+/// get `T` from `struct Name(pub Collection<T>)`
+fn entity_type(input: &DeriveInput) -> Result<&Type> {
+	let error = || Error::new_spanned(&input.ident, "repository must be tuple struct like `struct Name(pub Collection<T>)`");
+	let field = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+			_ => return Err(error())
+		},
+		_ => return Err(error())
+	};
+	let segment = match &field.ty {
+		Type::Path(path) => path.path.segments.last().ok_or_else(error)?,
+		_ => return Err(error())
+	};
+	match &segment.arguments {
+		PathArguments::AngleBracketed(args) if segment.ident == "Collection" => match args.args.first() {
+			Some(GenericArgument::Type(ty)) => Ok(ty),
+			_ => Err(error())
+		},
+		_ => Err(error())
+	}
+}
+
+/// Generate boilerplate of repository
+///
+/// + `From<&DatabaseWrapper>`, `Deref<Target=Collection<T>>` and `Repository<T, &DatabaseWrapper>`
+/// + accessor on `DatabaseWrapper` (named after collection unless `accessor` is set)
+/// + `INIT` index creation, list it in `repository::REPOSITORY_INITS` so `manager::database::preload` call it
+///
+/// # Example
+/// ```ignore
+/// #[derive(Repository)]
+/// #[repository(collection = "orders", accessor = "orders")]
+/// #[repository(index(field = "email", unique))]
+/// #[repository(index(field = "expire_at", expire_after = 0))]
+/// pub struct OrderRepository(pub Collection<Order>);
+/// ```
+///
+/// ## Index options
+/// + `field = ".."` indexed field (required)
+/// + `unique` / `sparse`
+/// + `expire_after = <seconds>` TTL index
+/// + `name = ".."` index name
+#[proc_macro_derive(Repository, attributes(repository))]
+pub fn derive_repository(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+	let args = Args::parse(input)?;
+	let entity = entity_type(input)?;
+	let name = &input.ident;
+	let collection = args.collection
+		.ok_or_else(|| Error::new_spanned(name, "missing `#[repository(collection = \"..\")]`"))?;
+	let accessor = match &args.accessor {
+		Some(accessor) => accessor.parse::<Ident>()?,
+		None => collection.parse::<Ident>()
+			.map_err(|_| Error::new_spanned(&collection, "collection name is not valid identifier, set `accessor = \"..\"`"))?,
+	};
+	let accessor_doc = format!("get [{}] with pre-configured collection", name);
+
+	let indexes = args.indexes.iter().map(|index| {
+		let field = &index.field;
+		let unique = index.unique.then(|| quote! { option.unique = Some(true); });
+		let sparse = index.sparse.then(|| quote! { option.sparse = Some(true); });
+		let expire_after = index.expire_after.as_ref()
+			.map(|secs| quote! { option.expire_after = Some(::std::time::Duration::from_secs(#secs)); });
+		let name = index.name.as_ref().map(|name| quote! { option.name = Some(#name.to_string()); });
+		quote! {
+			let mut option = ::mongodb::options::IndexOptions::default();
+			#unique #sparse #expire_after #name
+			crate::repository::Repository::ensure_index(&repo, ::mongodb::bson::doc! {#field: 1}, option).await?;
+		}
+	});
+
+	Ok(quote! {
+		impl ::std::convert::From<&crate::manager::DatabaseWrapper> for #name {
+			fn from(db: &crate::manager::DatabaseWrapper) -> Self {
+				#name(db.collection(#collection))
+			}
+		}
+
+		impl ::std::ops::Deref for #name {
+			type Target = ::mongodb::Collection<#entity>;
+
+			fn deref(&self) -> &Self::Target {
+				&self.0
+			}
+		}
+
+		impl crate::repository::Repository<#entity, &crate::manager::DatabaseWrapper> for #name {}
+
+		impl crate::manager::DatabaseWrapper {
+			#[doc = #accessor_doc]
+			pub fn #accessor(&self) -> #name {
+				self.into()
+			}
+		}
+
+		impl #name {
+			/// create(ensure) index declared by `#[repository(index(..))]`, listed in `REPOSITORY_INITS`
+			pub const INIT: crate::repository::RepositoryInit = crate::repository::RepositoryInit {
+				collection: #collection,
+				init: Self::init,
+			};
+
+			fn init(db: &crate::manager::DatabaseWrapper) -> crate::repository::InitFuture<'_> {
+				::std::boxed::Box::pin(async move {
+					#[allow(unused_variables)]
+					let repo = #name::from(db);
+					#(#indexes)*
+					::anyhow::Result::Ok(())
+				})
+			}
+		}
+	})
+}
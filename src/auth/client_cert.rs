@@ -0,0 +1,124 @@
+use std::any::Any;
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::{dev, Error, FromRequest, HttpRequest};
+use actix_web::dev::Extensions;
+use actix_web::rt::net::TcpStream;
+use actix_web::web::Data;
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+use crate::manager::DatabaseWrapper;
+use crate::schema::User;
+use crate::web::error::ApiError;
+
+/// DER of leaf certificate presented by client, already verified by rustls
+#[derive(Clone)]
+struct PeerCertificate(Vec<u8>);
+
+/// pass to `HttpServer::on_connect` to keep client certificate of tls connection for [ClientCert]
+pub fn on_connect(conn: &dyn Any, data: &mut Extensions) {
+	if let Some(stream) = conn.downcast_ref::<TlsStream<TcpStream>>() {
+		if let Some(cert) = stream.get_ref().1.peer_certificates().and_then(|it| it.first()) {
+			data.insert(PeerCertificate(cert.to_vec()));
+		}
+	}
+}
+
+/// caller authenticated by client certificate (mutual tls), require `HTTP_TLS_CLIENT_CA`
+///
+/// ## Request
+/// ```shell
+/// curl --cert client.pem --key client.key https://$HOST/endpoint
+/// ```
+/// ## Response
+/// + 401 if client doesn't present certificate or connection isn't tls
+#[derive(Clone, Debug)]
+pub struct ClientCert {
+	/// subject distinguished name like `CN=billing, O=Example`
+	pub subject: String,
+	/// common name (`CN`) of subject
+	pub common_name: Option<String>,
+	/// `DNS` entries of subject alternative name
+	pub dns_names: Vec<String>,
+	/// `URI` entries of subject alternative name (e.g. SPIFFE id)
+	pub uris: Vec<String>,
+	/// `email` entries of subject alternative name
+	pub emails: Vec<String>,
+}
+
+impl ClientCert {
+	fn parse(der: &[u8]) -> Option<Self> {
+		let (_, cert) = parse_x509_certificate(der).ok()?;
+		let subject = cert.subject();
+		let mut client = ClientCert {
+			subject: subject.to_string(),
+			common_name: subject.iter_common_name().next().and_then(|it| it.as_str().ok()).map(str::to_string),
+			dns_names: Vec::new(),
+			uris: Vec::new(),
+			emails: Vec::new(),
+		};
+		if let Ok(Some(san)) = cert.subject_alternative_name() {
+			for name in &san.value.general_names {
+				match name {
+					GeneralName::DNSName(name) => client.dns_names.push(name.to_string()),
+					GeneralName::URI(uri) => client.uris.push(uri.to_string()),
+					GeneralName::RFC822Name(email) => client.emails.push(email.to_string()),
+					_ => {}
+				}
+			}
+		}
+		Some(client)
+	}
+
+	/// name used to find user, common name or first DNS name if certificate doesn't have one
+	pub fn identity(&self) -> Option<&str> {
+		self.common_name.as_deref().or_else(|| self.dns_names.first().map(String::as_str))
+	}
+}
+
+impl FromRequest for ClientCert {
+	type Error = Error;
+	type Future = Ready<Result<Self, Self::Error>>;
+
+	fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+		let result = match req.conn_data::<PeerCertificate>() {
+			Some(cert) => ClientCert::parse(&cert.0).ok_or_else(|| ApiError::InvalidClientCertificate.into()),
+			None => Err(ApiError::MissingClientCertificate.into()),
+		};
+		ready(result)
+	}
+}
+
+/// user whose username match [ClientCert::identity], use for service account
+/// ```rust
+/// use actix_web::{get, Responder};
+/// use actix_mongo_jwt_web_template::auth::client_cert::CertUser;
+/// #[get("/internal")]
+/// async fn internal(CertUser(user): CertUser) -> impl Responder { user.username().to_string() }
+/// ```
+/// ## Response
+/// + 401 if certificate is missing or no user match it
+pub struct CertUser(pub User);
+
+async fn async_cert_user(db: Option<Data<DatabaseWrapper>>, cert: Result<ClientCert, Error>) -> Result<CertUser, Error> {
+	let identity = cert?.identity().map(str::to_string).ok_or(ApiError::InvalidClientCertificate)?;
+	let db = db.ok_or_else(|| ApiError::Internal(anyhow::anyhow!("database is not attached to app")))?;
+	match db.users().find_by_username(&identity).await.map_err(ApiError::from)? {
+		Some(user) => Ok(CertUser(user)),
+		None => Err(ApiError::InvalidClientCertificate.into()),
+	}
+}
+
+impl FromRequest for CertUser {
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self, Self::Error>>>>;
+
+	fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+		let cert = ClientCert::from_request(req, payload).into_inner();
+		let db = req.app_data::<Data<DatabaseWrapper>>().cloned();
+		Box::pin(async_cert_user(db, cert))
+	}
+}
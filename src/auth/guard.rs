@@ -0,0 +1,62 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::{dev, Error, FromRequest};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse};
+
+use crate::schema::Jwt;
+use crate::web::error::forbidden;
+
+/// requirement on jwt claims checked by [ClaimsGuardMiddleware]
+pub trait ClaimsRequirement: Copy + 'static {
+	/// true if requirement doesn't need token at all
+	fn is_empty(&self) -> bool;
+
+	/// check if token satisfy this requirement
+	fn check(&self, jwt: &Jwt) -> bool;
+}
+
+/// service created by middleware like [RequireRoles](super::role::RequireRoles),
+/// it reject request before it reach handler
+/// + 401 if token is missing, expired or invalid
+/// + 403 if token doesn't satisfy requirement
+pub struct ClaimsGuardMiddleware<S, R> {
+	service: Rc<S>,
+	requirement: R,
+}
+
+impl<S, R> ClaimsGuardMiddleware<S, R> {
+	/// wrap `service` with `requirement`
+	pub fn new(service: S, requirement: R) -> Self {
+		Self {
+			service: Rc::new(service),
+			requirement,
+		}
+	}
+}
+
+impl<S, B, R> Service<ServiceRequest> for ClaimsGuardMiddleware<S, R>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static,
+	      R: ClaimsRequirement {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		let service = self.service.clone();
+		let requirement = self.requirement;
+		Box::pin(async move {
+			if !requirement.is_empty() {
+				let jwt = Jwt::from_request(req.request(), &mut dev::Payload::None).await?;
+				if !requirement.check(&jwt) {
+					return Err(forbidden());
+				}
+			}
+			service.call(req).await
+		})
+	}
+}
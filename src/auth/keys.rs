@@ -0,0 +1,322 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::jwk::{AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, EllipticCurveKeyType, Jwk, JwkSet, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use simple_asn1::{ASN1Block, from_der};
+
+use crate::config::JwtKeyConfig;
+use crate::util::env::read_secret_file;
+
+// object identifiers used in SubjectPublicKeyInfo
+const OID_RSA: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+const OID_EC: &[u64] = &[1, 2, 840, 10045, 2, 1];
+const OID_P256: &[u64] = &[1, 2, 840, 10045, 3, 1, 7];
+const OID_P384: &[u64] = &[1, 3, 132, 0, 34];
+const OID_ED25519: &[u64] = &[1, 3, 101, 112];
+
+/// single key use to sign and / or verify jwt
+pub struct JwtKey {
+	/// key id, put in `kid` header so verifier know which key to use
+	pub kid: String,
+	/// algorithm used to sign token
+	pub algorithm: Algorithm,
+	/// None when key can only verify token (public key only)
+	pub(crate) encoding: Option<EncodingKey>,
+	pub(crate) decoding: DecodingKey,
+	/// public key as jwk, None for HMAC because secret must not be published
+	pub jwk: Option<Jwk>,
+}
+
+impl JwtKey {
+	/// create key from config, HMAC use `secret` and others use `private_key` and `public_key`
+	pub fn from_config(config: &JwtKeyConfig) -> Result<Self> {
+		let kid = config.kid.clone();
+		if is_hmac(config.algorithm) {
+			let secret = config.secret.as_ref().ok_or_else(|| anyhow!("missing jwt secret"))?;
+			Ok(Self::from_secret(kid, config.algorithm, secret))
+		} else {
+			let private = config.private_key.as_ref().ok_or_else(|| anyhow!("missing jwt private key"))?;
+			let public = config.public_key.as_ref().ok_or_else(|| anyhow!("missing jwt public key"))?;
+			Self::from_pem(kid, config.algorithm, Some(private), public)
+		}
+	}
+
+	/// create HMAC key from secret
+	pub fn from_secret(kid: String, algorithm: Algorithm, secret: &[u8]) -> Self {
+		Self {
+			kid,
+			algorithm,
+			encoding: Some(EncodingKey::from_secret(secret)),
+			decoding: DecodingKey::from_secret(secret),
+			jwk: None,
+		}
+	}
+
+	/// create key from PEM encoded private and public key, key without private key can only verify token
+	pub fn from_pem(kid: String, algorithm: Algorithm, private: Option<&[u8]>, public: &[u8]) -> Result<Self> {
+		let (encoding, decoding) = match algorithm {
+			Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 |
+			Algorithm::PS256 | Algorithm::PS384 | Algorithm::PS512 => {
+				(private.map(EncodingKey::from_rsa_pem).transpose()?, DecodingKey::from_rsa_pem(public)?)
+			}
+			Algorithm::ES256 | Algorithm::ES384 => {
+				(private.map(EncodingKey::from_ec_pem).transpose()?, DecodingKey::from_ec_pem(public)?)
+			}
+			Algorithm::EdDSA => {
+				(private.map(EncodingKey::from_ed_pem).transpose()?, DecodingKey::from_ed_pem(public)?)
+			}
+			_ => bail!("{:?} is not asymmetric algorithm", algorithm)
+		};
+		let mut jwk = public_jwk(algorithm, public)?;
+		jwk.common.key_id = Some(kid.clone());
+		Ok(Self {
+			kid,
+			algorithm,
+			encoding,
+			decoding,
+			jwk: Some(jwk),
+		})
+	}
+
+	/// get header for token signed by this key
+	pub fn header(&self) -> Header {
+		let mut header = Header::new(self.algorithm);
+		header.kid = Some(self.kid.clone());
+		header
+	}
+
+	/// sign claims with this key
+	pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String> {
+		let encoding = self.encoding.as_ref().ok_or_else(|| anyhow!("key `{}` can't sign token", self.kid))?;
+		Ok(jsonwebtoken::encode(&self.header(), claims, encoding)?)
+	}
+
+	/// verify signature of token and decode claims, `validation.algorithms` is replaced by algorithm of this key
+	pub fn decode<T: DeserializeOwned>(&self, token: &str, validation: &Validation) -> jsonwebtoken::errors::Result<T> {
+		let mut validation = validation.clone();
+		validation.algorithms = vec![self.algorithm];
+		Ok(jsonwebtoken::decode::<T>(token, &self.decoding, &validation)?.claims)
+	}
+}
+
+/// entry of key ring file
+#[derive(Deserialize)]
+struct KeyEntry {
+	kid: String,
+	algorithm: String,
+	secret: Option<String>,
+	secret_file: Option<String>,
+	private_key: Option<String>,
+	private_key_file: Option<String>,
+	public_key: Option<String>,
+	public_key_file: Option<String>,
+}
+
+/// key ring file
+/// ```json
+/// {
+///   "active": "2024-02",
+///   "keys": [
+///     {"kid": "2024-02", "algorithm": "ES256", "private_key_file": "/keys/2024-02.pem", "public_key_file": "/keys/2024-02.pub"},
+///     {"kid": "2024-01", "algorithm": "ES256", "public_key_file": "/keys/2024-01.pub"}
+///   ]
+/// }
+/// ```
+#[derive(Deserialize)]
+struct KeyRingFile {
+	active: String,
+	keys: Vec<KeyEntry>,
+}
+
+/// set of keys, new token is signed by active key and token signed by any key in the ring is accepted
+///
+/// to rotate key, add new key to the ring and mark it active, then remove (retire) old key
+/// once every token signed by it has expired
+pub struct KeyRing {
+	active: usize,
+	keys: Vec<JwtKey>,
+}
+
+impl KeyRing {
+	/// load key ring from `key_ring_file` if set, otherwise create ring with single key from config
+	pub fn from_config(config: &JwtKeyConfig) -> Result<Self> {
+		match &config.key_ring_file {
+			Some(path) => Self::from_file(path),
+			None => {
+				let key = JwtKey::from_config(config)?;
+				Self::new(key.kid.clone(), vec![key])
+			}
+		}
+	}
+
+	/// load key ring from json file
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let data = std::fs::read(path).with_context(|| format!("failed to read key ring {}", path.display()))?;
+		let file: KeyRingFile = serde_json::from_slice(&data).with_context(|| format!("invalid key ring {}", path.display()))?;
+		let keys = file.keys.into_iter()
+			.map(|entry| {
+				let kid = entry.kid.clone();
+				load_entry(entry).with_context(|| format!("failed to load key `{}`", kid))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Self::new(file.active, keys)
+	}
+
+	/// create key ring, `active` must be kid of key which has private key / secret
+	pub fn new(active: String, keys: Vec<JwtKey>) -> Result<Self> {
+		for (i, key) in keys.iter().enumerate() {
+			if keys[..i].iter().any(|it| it.kid == key.kid) {
+				bail!("duplicated kid `{}`", key.kid)
+			}
+		}
+		let active = keys.iter().position(|it| it.kid == active)
+			.ok_or_else(|| anyhow!("active key `{}` is not in key ring", active))?;
+		if keys[active].encoding.is_none() {
+			bail!("active key `{}` can't sign token (missing private key)", keys[active].kid)
+		}
+		Ok(Self { active, keys })
+	}
+
+	/// get key used to sign new token
+	pub fn active(&self) -> &JwtKey {
+		&self.keys[self.active]
+	}
+
+	/// find key to verify token, token without `kid` is verified by active key
+	pub fn find(&self, kid: Option<&str>) -> Option<&JwtKey> {
+		match kid {
+			Some(kid) => self.keys.iter().find(|it| it.kid == kid),
+			None => Some(self.active()),
+		}
+	}
+
+	/// get public key set to publish at `/.well-known/jwks.json`
+	pub fn jwks(&self) -> JwkSet {
+		JwkSet { keys: self.keys.iter().filter_map(|it| it.jwk.clone()).collect() }
+	}
+}
+
+/// parse algorithm name like `HS512` or `ES256`
+fn parse_algorithm(alg: &str) -> Result<Algorithm> {
+	Algorithm::from_str(alg).map_err(|_| anyhow!("unsupported jwt algorithm {}", alg))
+}
+
+/// check if algorithm use shared secret
+fn is_hmac(algorithm: Algorithm) -> bool {
+	matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+/// load key from key ring file entry
+fn load_entry(entry: KeyEntry) -> Result<JwtKey> {
+	let algorithm = parse_algorithm(entry.algorithm.as_str())?;
+	let read = |value: Option<String>, file: Option<String>| -> Result<Option<Vec<u8>>> {
+		match (value, file) {
+			(Some(value), _) => Ok(Some(value.into_bytes())),
+			(None, Some(file)) => Ok(Some(read_secret_file(&file)?)),
+			(None, None) => Ok(None),
+		}
+	};
+
+	if is_hmac(algorithm) {
+		let secret = read(entry.secret, entry.secret_file)?
+			.filter(|it| !it.is_empty())
+			.ok_or_else(|| anyhow!("missing `secret` or `secret_file`"))?;
+		Ok(JwtKey::from_secret(entry.kid, algorithm, &secret))
+	} else {
+		let private = read(entry.private_key, entry.private_key_file)?;
+		let public = read(entry.public_key, entry.public_key_file)?
+			.ok_or_else(|| anyhow!("missing `public_key` or `public_key_file`"))?;
+		JwtKey::from_pem(entry.kid, algorithm, private.as_deref(), &public)
+	}
+}
+
+/// convert PEM encoded public key (SubjectPublicKeyInfo or PKCS#1 RSA) to jwk
+fn public_jwk(algorithm: Algorithm, public: &[u8]) -> Result<Jwk> {
+	let pem = pem::parse(public)?;
+	let params = match pem.tag.as_str() {
+		"PUBLIC KEY" => spki_params(&pem.contents)?,
+		"RSA PUBLIC KEY" => rsa_params(&pem.contents)?,
+		tag => bail!("unsupported public key type `{}`", tag),
+	};
+	Ok(Jwk {
+		common: CommonParameters {
+			public_key_use: Some(PublicKeyUse::Signature),
+			algorithm: Some(algorithm),
+			..Default::default()
+		},
+		algorithm: params,
+	})
+}
+
+/// read SubjectPublicKeyInfo
+fn spki_params(der: &[u8]) -> Result<AlgorithmParameters> {
+	let blocks = from_der(der)?;
+	let (alg, key) = match blocks.first() {
+		Some(ASN1Block::Sequence(_, items)) => match (items.first(), items.get(1)) {
+			(Some(ASN1Block::Sequence(_, alg)), Some(ASN1Block::BitString(_, _, key))) => (alg, key),
+			_ => bail!("invalid public key"),
+		},
+		_ => bail!("invalid public key"),
+	};
+	let oid = |block: Option<&ASN1Block>| match block {
+		Some(ASN1Block::ObjectIdentifier(_, oid)) => Ok(oid.as_vec::<u64>()?),
+		_ => Err(anyhow!("invalid public key algorithm")),
+	};
+
+	let key_oid = oid(alg.first())?;
+	if key_oid == OID_RSA {
+		rsa_params(key)
+	} else if key_oid == OID_EC {
+		let curve_oid = oid(alg.get(1))?;
+		let (curve, size) = if curve_oid == OID_P256 {
+			(EllipticCurve::P256, 32)
+		} else if curve_oid == OID_P384 {
+			(EllipticCurve::P384, 48)
+		} else {
+			bail!("unsupported elliptic curve")
+		};
+		// only uncompressed point (0x04 || x || y) is supported
+		if key.len() != 1 + size * 2 || key[0] != 0x04 {
+			bail!("invalid elliptic curve public key")
+		}
+		Ok(AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+			key_type: EllipticCurveKeyType::EC,
+			curve,
+			x: URL_SAFE_NO_PAD.encode(&key[1..1 + size]),
+			y: URL_SAFE_NO_PAD.encode(&key[1 + size..]),
+		}))
+	} else if key_oid == OID_ED25519 {
+		Ok(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+			key_type: OctetKeyPairType::OctetKeyPair,
+			curve: EllipticCurve::Ed25519,
+			x: URL_SAFE_NO_PAD.encode(key),
+		}))
+	} else {
+		bail!("unsupported public key algorithm")
+	}
+}
+
+/// read PKCS#1 RSAPublicKey
+fn rsa_params(der: &[u8]) -> Result<AlgorithmParameters> {
+	let blocks = from_der(der)?;
+	match blocks.first() {
+		Some(ASN1Block::Sequence(_, items)) => match (items.first(), items.get(1)) {
+			(Some(ASN1Block::Integer(_, n)), Some(ASN1Block::Integer(_, e))) => {
+				Ok(AlgorithmParameters::RSA(RSAKeyParameters {
+					key_type: RSAKeyType::RSA,
+					n: URL_SAFE_NO_PAD.encode(n.to_bytes_be().1),
+					e: URL_SAFE_NO_PAD.encode(e.to_bytes_be().1),
+				}))
+			}
+			_ => bail!("invalid rsa public key"),
+		},
+		_ => bail!("invalid rsa public key"),
+	}
+}
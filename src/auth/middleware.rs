@@ -1,91 +1,122 @@
 use std::future::Future;
 use std::ops::Deref;
 use std::pin::Pin;
-use std::str::FromStr;
-#[cfg(feature = "basic-auth")]
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use actix_web::{dev, Error, FromRequest, HttpRequest, web};
-use actix_web::error::ErrorUnauthorized;
-#[cfg(feature = "basic-auth")]
 use actix_web::web::Data;
 use anyhow::Result;
-use chrono::Duration;
+#[cfg(feature = "basic-auth")]
+use base64::Engine;
 use futures::future::ready;
-use jsonwebtoken::{Algorithm, decode, DecodingKey, encode, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode_header, Validation};
+use jsonwebtoken::errors::ErrorKind;
+use mongodb::bson::oid::ObjectId;
 
+use crate::config::{self, AuthConfig};
 use crate::manager::DatabaseWrapper;
-use crate::schema::Jwt;
-use crate::util::env::env;
-use crate::util::time::{timestamp_u64, TimestampExt};
-
-use super::login_by_username;
-
-const JWT_EXPIRE_HOUR: u64 = 24;
-
-#[cfg(feature = "static-jwt-secret")]
-static SECRET: &'static str = include_str!("../../jwt_secret");
-
-/// get expire timestamp for jwt
-fn jwt_expire_time() -> u64 {
-	Duration::hours(
-		env("AUTH_JWT_EXPIRE_HOUR")
-			// load as u64 because expire time -1 hour doesn't make sense
-			.and_then(|it| u64::from_str(it.as_str()).ok())
-			// minimum token time 1 hour
-			.map(|hours| if hours < 1 { 1 } else { 0 })
-			.unwrap_or(JWT_EXPIRE_HOUR)
-			as i64)
-		// cast to u64 due we don't need to use timestamp < 0 it's older than 1970
-		.timestamp_from_now() as u64
+use crate::metrics::record_login;
+use crate::schema::{Jwt, User};
+use crate::util::time::{timestamp_sec, timestamp_u64};
+use crate::web::error::ApiError;
+
+use super::keys::KeyRing;
+use super::{login_by_username, LoginError};
+use super::permission::resolve_permissions;
+use super::refresh::{issue_token_pair, TokenPair};
+use super::revocation::is_revoked;
+
+/// get expire timestamp for jwt (seconds)
+pub(crate) fn jwt_expire_time() -> u64 {
+	timestamp_sec() + config::get().auth.jwt_expire
 }
 
-/// get default header for jwt
-fn default_jwt_header() -> Header {
-	let mut header = Header::default();
-	// sha512 provide better security and faster on large data
-	header.alg = Algorithm::HS512;
-	header
+/// validation for `exp`, `nbf`, `iss` and `aud`, algorithm is set by key
+fn validation(config: &AuthConfig) -> Validation {
+	let mut validation = Validation::default();
+	validation.leeway = config.jwt_leeway;
+	validation.validate_nbf = true;
+	validation.set_required_spec_claims(&["exp", "iat", "sub"]);
+	if let Some(issuer) = &config.jwt_issuer {
+		validation.set_issuer(&[issuer]);
+	}
+	if !config.jwt_audience.is_empty() {
+		validation.set_audience(&config.jwt_audience);
+	}
+	validation
 }
 
 lazy_static::lazy_static! {
-	static ref JWT_KEY: (EncodingKey, DecodingKey) = {
-		#[cfg(not(feature = "static-jwt-secret"))]
-		{
-			use crate::util::env::raw_env;
-			let data = raw_env("AUTH_JWT_SECRET").unwrap_or_default();
-			if data.is_empty() {
-				panic!("please set `JWT_SECRET` in environment variable")
-			}
-			(EncodingKey::from_secret(&data), DecodingKey::from_secret(&data))
-		}
-		#[cfg(feature = "static-jwt-secret")]
-		{
-			let data = SECRET.as_bytes();
-			if data.is_empty() {
-				panic!("please add secret to `jwt_secret` file")
-			}
-			(EncodingKey::from_secret(&data), DecodingKey::from_secret(&data))
-		}
-	};
+	static ref KEY_RING: RwLock<Arc<KeyRing>> = RwLock::new(Arc::new(
+		// key is already checked when config is loaded
+		KeyRing::from_config(&config::get().auth.jwt_key).unwrap_or_else(|err| panic!("failed to load jwt key: {:#}", err))
+	));
+	static ref VALIDATION: Validation = validation(&config::get().auth);
 }
 
-/// this function use to create JWT token from args (this may call from different authenticate method)
-pub async fn create_token(sub: String) -> Result<String> {
-	Ok(web::block(move || {
+/// get current key ring used to sign and verify jwt
+pub fn key_ring() -> Arc<KeyRing> {
+	KEY_RING.read().unwrap().clone()
+}
+
+/// load key ring again from `AUTH_JWT_KEY_RING_FILE`, current key ring is kept if it failed to load
+pub fn reload_key_ring() -> Result<()> {
+	let ring = KeyRing::from_config(&config::get().auth.jwt_key)?;
+	*KEY_RING.write().unwrap() = Arc::new(ring);
+	Ok(())
+}
+
+/// verify token with key matching its `kid` header
+fn decode_token(token: &str) -> jsonwebtoken::errors::Result<Jwt> {
+	let ring = key_ring();
+	let kid = decode_header(token)?.kid;
+	// unknown or retired key
+	let key = ring.find(kid.as_deref()).ok_or(ErrorKind::InvalidSignature)?;
+	key.decode(token, &VALIDATION)
+}
+
+/// this function use to create JWT token for user (this may call from different authenticate method)
+pub async fn create_token(db: impl Deref<Target=DatabaseWrapper>, user: &User) -> Result<String> {
+	Ok(create_token_with_exp(db, user).await?.0)
+}
+
+/// same as [create_token] but also return expire timestamp of the token
+pub async fn create_token_with_exp(db: impl Deref<Target=DatabaseWrapper>, user: &User) -> Result<(String, u64)> {
+	let scope = resolve_permissions(db, user).await?.join(" ");
+	let ring = key_ring();
+	let sub = user.id_ref().to_string();
+	let roles = user.roles().to_vec();
+	web::block(move || {
+		let now_ms = timestamp_u64();
+		let now = now_ms / 1000;
 		let exp = jwt_expire_time();
+		let config = &config::get().auth;
 		let claims = Jwt {
+			iss: config.jwt_issuer.clone(),
 			sub,
+			aud: config.jwt_audience.first().cloned(),
 			exp,
+			nbf: now,
+			iat: now,
+			iat_ms: now_ms,
+			jti: ObjectId::new().to_hex(),
+			roles,
+			scope,
 		};
-		encode(&default_jwt_header(), &claims, &JWT_KEY.0)
-	}).await??)
+		ring.active().encode(&claims).map(|token| (token, exp))
+	}).await?
 }
 
-/// login with `username` and `password` and return JWT token
-pub async fn login_as_token(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str) -> Result<String> {
-	let user = login_by_username(db, username, password).await?;
-	Ok(create_token(user.id_ref().to_string()).await?)
+/// login with `username` and `password` and return JWT token with refresh token
+pub async fn login_as_token(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str) -> Result<TokenPair, LoginError> {
+	let user = login_by_username(db.deref(), username, password).await;
+	// database error isn't a failed login
+	match &user {
+		Ok(_) => record_login(true),
+		Err(LoginError::InvalidCredentials) => record_login(false),
+		Err(LoginError::Internal(_)) => {}
+	}
+	Ok(issue_token_pair(db, &user?).await?)
 }
 
 /// ## Enabling
@@ -107,20 +138,45 @@ async fn async_basic_auth(db: Arc<DatabaseWrapper>, data: Option<String>) -> JWT
 	if let Some(data) = data {
 		let mut split = data.splitn(3, ":");
 		if let (Some(username), Some(password)) = (split.next(), split.next()) {
-			if let Ok(user) = login_by_username(db.as_ref(), username, password).await {
-				return Ok(Jwt {
-					sub: user.id_ref().to_string(),
-					exp: u64::MAX,// it doesn't even generate jwt token, unused
-				});
-			}
+			// wrong password is `invalid_credentials`, database error is `internal_error`
+			let user = login_by_username(db.as_ref(), username, password).await.map_err(ApiError::from)?;
+			let scope = resolve_permissions(db.as_ref(), &user).await.map_err(ApiError::from)?.join(" ");
+			let now_ms = timestamp_u64();
+			let now = now_ms / 1000;
+			return Ok(Jwt {
+				iss: None,
+				sub: user.id_ref().to_string(),
+				aud: None,
+				exp: u64::MAX,// it doesn't even generate jwt token, unused
+				nbf: now,
+				iat: now,
+				iat_ms: now_ms,
+				jti: String::new(),// can't be revoked, unused
+				roles: user.roles().to_vec(),
+				scope,
+			});
 		}
 	}
 
-	JWTResult::Err(ErrorUnauthorized("Invalid username or password!"))
+	JWTResult::Err(ApiError::InvalidCredentials.into())
 }
 
 type JWTResult = Result<Jwt, Error>;
 
+/// reject token if it has been revoked
+async fn async_check_revoked(db: Option<Data<DatabaseWrapper>>, claims: Jwt) -> JWTResult {
+	let db = match db {
+		Some(db) => db,
+		// database is not attached to app, nothing to check against
+		None => return Ok(claims)
+	};
+	match is_revoked(db.get_ref(), &claims).await {
+		Ok(false) => Ok(claims),
+		Ok(true) => Err(ApiError::RevokedToken.into()),
+		Err(err) => Err(ApiError::Internal(err.context("failed to check token revocation")).into()),
+	}
+}
+
 impl FromRequest for Jwt {
 	type Error = Error;
 	type Future = Pin<Box<dyn Future<Output=JWTResult>>>;
@@ -129,7 +185,7 @@ impl FromRequest for Jwt {
 		let auth = req.headers().get("Authorization");
 		match auth {
 			Some(auth) => {
-				if auth.len() < 8 { return Box::pin(ready(Err(ErrorUnauthorized("Invalid token!")))); }
+				if auth.len() < 8 { return Box::pin(ready(Err(ApiError::InvalidToken.into()))); }
 
 				let mut split = auth.to_str().unwrap_or("").splitn(2, " ");
 				let auth_type = split.next();
@@ -137,39 +193,31 @@ impl FromRequest for Jwt {
 				let token = split.next().unwrap_or("").trim();
 
 				if token.is_empty() {
-					return Box::pin(ready(Err(ErrorUnauthorized("Invalid token!"))));
+					return Box::pin(ready(Err(ApiError::InvalidToken.into())));
 				}
 				match auth_type {
 					Some("Bearer") => {
-						match decode::<Jwt>(
-							token,
-							&JWT_KEY.1,
-							&Validation::new(Algorithm::HS512),
-						) {
-							Ok(data) => {
-								let claims = data.claims;
-								if claims.exp > timestamp_u64() {
-									Box::pin(ready(Ok(claims)))
-								} else {
-									Box::pin(ready(Err(ErrorUnauthorized("Expired token!"))))
-								}
-							}
-							Err(_) => {
-								Box::pin(ready(Err(ErrorUnauthorized("Invalid token!"))))
+						// `exp`, `nbf`, `iss` and `aud` are checked by jsonwebtoken
+						match decode_token(token) {
+							Ok(claims) => {
+								tracing::Span::current().record("user_id", claims.sub.as_str());
+								let db = req.app_data::<Data<DatabaseWrapper>>().cloned();
+								Box::pin(async_check_revoked(db, claims))
 							}
+							Err(err) => Box::pin(ready(Err(ApiError::from(err).into()))),
 						}
 					}
 					#[cfg(feature = "basic-auth")]
 					Some("Basic") => {
 						let db = req.app_data::<Data<DatabaseWrapper>>().unwrap().clone();
-						Box::pin(async_basic_auth(db.into_inner(), base64::decode(token).ok().and_then(|it| String::from_utf8(it).ok())))
+						Box::pin(async_basic_auth(db.into_inner(), base64::engine::general_purpose::STANDARD.decode(token).ok().and_then(|it| String::from_utf8(it).ok())))
 					}
 					_ => {
-						Box::pin(ready(Err(ErrorUnauthorized("Invalid token!"))))
+						Box::pin(ready(Err(ApiError::InvalidToken.into())))
 					}
 				}
 			}
-			None => Box::pin(ready(Err(ErrorUnauthorized("Missing token!"))))
+			None => Box::pin(ready(Err(ApiError::MissingToken.into())))
 		}
 	}
 }
\ No newline at end of file
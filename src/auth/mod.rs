@@ -1,20 +1,191 @@
 use std::ops::Deref;
 
-use anyhow::bail;
 use anyhow::Result;
 
+use crate::config;
 use crate::manager::DatabaseWrapper;
+use crate::repository::{Repository, RepositoryError};
 use crate::schema::User;
 
 /// this module contains middleware / from handle for actix
 pub mod middleware;
 
+/// this module load key used to sign / verify jwt
+pub mod keys;
+
+/// this module contains refresh token issuing and rotation
+pub mod refresh;
+
+/// this module contains server-side jwt revocation (logout)
+pub mod revocation;
+
+/// this module contains middleware rejecting request by jwt claims
+pub mod guard;
+
+/// this module contains role based access control extractor / middleware
+pub mod role;
+
+/// this module contains permission (scope) resolution and extractor / middleware
+pub mod permission;
+
+/// this module contains client certificate (mutual tls) extractor
+pub mod client_cert;
+
+/// minimum length of username (characters)
+pub const USERNAME_MIN_LEN: usize = 3;
+/// maximum length of username (characters)
+pub const USERNAME_MAX_LEN: usize = 32;
+/// minimum length of password (characters)
+pub const PASSWORD_MIN_LEN: usize = 8;
+/// bcrypt will only use first 72 bytes of password
+pub const PASSWORD_MAX_LEN: usize = 72;
+
+/// reason why login was rejected
+pub enum LoginError {
+	/// username doesn't exist or password is wrong
+	InvalidCredentials,
+	/// database or signing error
+	Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for LoginError {
+	fn from(err: anyhow::Error) -> Self {
+		LoginError::Internal(err)
+	}
+}
+
 /// login user using username and password
-pub async fn login_by_username(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str) -> Result<User> {
-	if let Some(user) = db.users().find_by_username(username).await {
-		if user.verify_password(password).await {
-			return Ok(user);
-		}
-	}
-	bail!("invalid username or password?")
-}
\ No newline at end of file
+#[tracing::instrument(skip(db, password))]
+pub async fn login_by_username(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str) -> Result<User, LoginError> {
+	let user = db.users().find_by_username(username).await.map_err(|err| LoginError::Internal(err.into()))?;
+	match user {
+		Some(user) if user.verify_password(password).await => Ok(user),
+		_ => Err(LoginError::InvalidCredentials),
+	}
+}
+
+/// reason why registration was rejected
+pub enum RegisterError {
+	/// username or password doesn't meet requirement
+	Invalid(String),
+	/// username is already used by other user
+	UsernameTaken,
+	/// database or hashing error
+	Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RegisterError {
+	fn from(err: anyhow::Error) -> Self {
+		RegisterError::Internal(err)
+	}
+}
+
+/// check if self-service registration is enabled (`AUTH_REGISTER_ENABLE`, default: enabled)
+pub fn register_enabled() -> bool {
+	config::get().auth.register_enable
+}
+
+/// check if username is acceptable
+/// + length between [USERNAME_MIN_LEN] and [USERNAME_MAX_LEN] characters
+/// + contains only ascii letter, digit, `_`, `-` or `.`
+pub fn validate_username(username: &str) -> Result<(), String> {
+	let len = username.chars().count();
+	if !(USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len) {
+		return Err(format!("username must be between {} and {} characters", USERNAME_MIN_LEN, USERNAME_MAX_LEN));
+	}
+	if !username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+		return Err("username may only contain letters, digits, '_', '-' or '.'".to_string());
+	}
+	Ok(())
+}
+
+/// check if password is acceptable
+/// + at least [PASSWORD_MIN_LEN] characters
+/// + at most [PASSWORD_MAX_LEN] bytes (bcrypt ignore the rest)
+pub fn validate_password(password: &str) -> Result<(), String> {
+	if password.chars().count() < PASSWORD_MIN_LEN {
+		return Err(format!("password must be at least {} characters", PASSWORD_MIN_LEN));
+	}
+	if password.len() > PASSWORD_MAX_LEN {
+		return Err(format!("password must not be longer than {} bytes", PASSWORD_MAX_LEN));
+	}
+	Ok(())
+}
+
+/// create new user with username and password and insert it to database
+pub async fn register_user(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str) -> Result<User, RegisterError> {
+	create_user(db, username, password, &[]).await
+}
+
+/// same as [register_user] but user also get `roles` (use by admin)
+pub async fn create_user(db: impl Deref<Target=DatabaseWrapper>, username: &str, password: &str, roles: &[String]) -> Result<User, RegisterError> {
+	validate_username(username).map_err(RegisterError::Invalid)?;
+	validate_password(password).map_err(RegisterError::Invalid)?;
+
+	let mut user = User::new(username.to_string());
+	for role in roles {
+		user.add_role(role.as_str());
+	}
+	if !user.set_password(password).await {
+		return Err(anyhow::anyhow!("failed to hash password").into());
+	}
+
+	// rely on unique index of `username` instead of checking before insert (race condition)
+	match db.users().insert(&user).await {
+		Ok(_) => Ok(user),
+		Err(RepositoryError::DuplicateKey(_)) => Err(RegisterError::UsernameTaken),
+		Err(err) => Err(anyhow::Error::from(err).into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn username_length_boundary() {
+		assert!(validate_username(&"a".repeat(USERNAME_MIN_LEN - 1)).is_err());
+		assert!(validate_username(&"a".repeat(USERNAME_MIN_LEN)).is_ok());
+		assert!(validate_username(&"a".repeat(USERNAME_MAX_LEN)).is_ok());
+		assert!(validate_username(&"a".repeat(USERNAME_MAX_LEN + 1)).is_err());
+	}
+
+	#[test]
+	fn username_message_use_limit() {
+		let message = validate_username("a").unwrap_err();
+		assert!(message.contains(&USERNAME_MIN_LEN.to_string()));
+		assert!(message.contains(&USERNAME_MAX_LEN.to_string()));
+	}
+
+	#[test]
+	fn username_charset() {
+		assert!(validate_username("user_name-1.0").is_ok());
+		assert!(validate_username("user name").is_err());
+		assert!(validate_username("usér").is_err());
+	}
+
+	#[test]
+	fn password_length_boundary() {
+		assert!(validate_password(&"a".repeat(PASSWORD_MIN_LEN - 1)).is_err());
+		assert!(validate_password(&"a".repeat(PASSWORD_MIN_LEN)).is_ok());
+		assert!(validate_password(&"a".repeat(PASSWORD_MAX_LEN)).is_ok());
+		assert!(validate_password(&"a".repeat(PASSWORD_MAX_LEN + 1)).is_err());
+	}
+
+	#[test]
+	fn password_min_count_characters() {
+		// 2 bytes each, enough bytes but not enough characters
+		assert!(validate_password(&"é".repeat(PASSWORD_MIN_LEN - 1)).is_err());
+		assert!(validate_password(&"é".repeat(PASSWORD_MIN_LEN)).is_ok());
+	}
+
+	#[test]
+	fn password_max_count_bytes() {
+		// 36 characters of 2 bytes is exactly the limit
+		let password = "é".repeat(PASSWORD_MAX_LEN / 2);
+		assert_eq!(password.len(), PASSWORD_MAX_LEN);
+		assert!(validate_password(&password).is_ok());
+		let message = validate_password(&format!("{}a", password)).unwrap_err();
+		assert!(message.contains(&PASSWORD_MAX_LEN.to_string()));
+	}
+}
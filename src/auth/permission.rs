@@ -0,0 +1,141 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+
+use actix_web::{dev, Error, FromRequest, HttpRequest};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use anyhow::Result;
+use futures::future::{ready, Ready};
+
+use crate::manager::DatabaseWrapper;
+use crate::schema::{Jwt, User};
+use crate::web::error::forbidden;
+
+use super::guard::{ClaimsGuardMiddleware, ClaimsRequirement};
+
+/// set of permissions required by [RequirePermission], use [permissions!](crate::permissions) to declare
+pub trait PermissionSet {
+	/// every permission in this list is required
+	const REQUIRED: &'static [&'static str];
+}
+
+/// declare [PermissionSet] to use with [RequirePermission]
+/// # Example
+/// ```rust
+/// use actix_web::{get, Responder};
+/// use actix_mongo_jwt_web_template::auth::permission::RequirePermission;
+/// use actix_mongo_jwt_web_template::permissions;
+/// permissions!(pub ReadOrders = ["orders:read"]);
+///
+/// #[get("/orders")]
+/// async fn list_orders(jwt: RequirePermission<ReadOrders>) -> impl Responder { "" }
+/// ```
+#[macro_export]
+macro_rules! permissions {
+	($(#[$meta:meta])* $vis:vis $name:ident = [$($permission:literal),* $(,)?]) => {
+		$(#[$meta])*
+		$vis struct $name;
+
+		impl $crate::auth::permission::PermissionSet for $name {
+			const REQUIRED: &'static [&'static str] = &[$($permission),*];
+		}
+	};
+}
+
+/// collect permissions of user, from user itself and every role of user
+pub async fn resolve_permissions(db: impl Deref<Target=DatabaseWrapper>, user: &User) -> Result<Vec<String>> {
+	let mut permissions = user.permissions().to_vec();
+	if !user.roles().is_empty() {
+		for role in db.roles().find_by_names(user.roles()).await? {
+			permissions.extend(role.permissions);
+		}
+	}
+	permissions.sort_unstable();
+	permissions.dedup();
+	Ok(permissions)
+}
+
+/// extractor which require token having every permission in `P`
+/// + 401 if token is missing, expired or invalid
+/// + 403 if token doesn't have required permission
+pub struct RequirePermission<P: PermissionSet> {
+	jwt: Jwt,
+	_permission: PhantomData<fn() -> P>,
+}
+
+impl<P: PermissionSet> RequirePermission<P> {
+	/// get claims of token
+	pub fn into_inner(self) -> Jwt {
+		self.jwt
+	}
+}
+
+impl<P: PermissionSet> Deref for RequirePermission<P> {
+	type Target = Jwt;
+
+	fn deref(&self) -> &Self::Target {
+		&self.jwt
+	}
+}
+
+impl<P: PermissionSet + 'static> FromRequest for RequirePermission<P> {
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self, Error>>>>;
+
+	fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+		let jwt = Jwt::from_request(req, payload);
+		Box::pin(async move {
+			let jwt = jwt.await?;
+			if P::REQUIRED.iter().all(|it| jwt.has_permission(it)) {
+				Ok(Self { jwt, _permission: PhantomData })
+			} else {
+				Err(forbidden())
+			}
+		})
+	}
+}
+
+/// middleware which require token having every permission in `permissions` for every route it wraps,
+/// empty `permissions` allow every request
+/// # Example
+/// ```rust
+/// use actix_web::web;
+/// use actix_mongo_jwt_web_template::auth::permission::RequirePermissions;
+/// web::scope("orders").wrap(RequirePermissions::all(&["orders:read"]));
+/// ```
+#[derive(Clone, Copy)]
+pub struct RequirePermissions {
+	permissions: &'static [&'static str],
+}
+
+impl RequirePermissions {
+	/// require every permission in `permissions`
+	pub fn all(permissions: &'static [&'static str]) -> Self {
+		Self { permissions }
+	}
+}
+
+impl ClaimsRequirement for RequirePermissions {
+	fn is_empty(&self) -> bool {
+		self.permissions.is_empty()
+	}
+
+	fn check(&self, jwt: &Jwt) -> bool {
+		self.permissions.iter().all(|it| jwt.has_permission(it))
+	}
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermissions
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Transform = ClaimsGuardMiddleware<S, Self>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(ClaimsGuardMiddleware::new(service, *self)))
+	}
+}
@@ -0,0 +1,141 @@
+use std::ops::Deref;
+
+use anyhow::Result;
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::manager::DatabaseWrapper;
+use crate::repository::Repository;
+use crate::schema::{RefreshToken, User};
+use crate::util::time::timestamp_u64;
+
+use super::middleware::create_token_with_exp;
+
+/// access token and refresh token issued to client
+pub struct TokenPair {
+	/// jwt access token
+	pub token: String,
+	/// expire timestamp of access token (seconds)
+	pub expires_at: u64,
+	/// opaque refresh token use to get new token pair
+	pub refresh_token: String,
+	/// expire timestamp of refresh token (seconds)
+	pub refresh_expires_at: u64,
+}
+
+/// reason why refresh token can't be exchanged
+pub enum RefreshError {
+	/// token doesn't exist, expired or revoked
+	Invalid,
+	/// token was already exchanged, whole family has been revoked
+	Reused,
+	/// database or signing error
+	Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for RefreshError {
+	fn from(err: anyhow::Error) -> Self {
+		RefreshError::Internal(err)
+	}
+}
+
+/// get expire timestamp for refresh token (milliseconds)
+fn refresh_expire_time() -> u64 {
+	timestamp_u64() + config::get().auth.refresh_expire * 1000
+}
+
+/// generate random opaque token (256 bits as hex)
+fn generate_token() -> String {
+	let bytes: [u8; 32] = rand::thread_rng().gen();
+	hex::encode(bytes)
+}
+
+/// hash token before store / lookup, token already has high entropy so plain sha256 is enough
+fn hash_token(token: &str) -> String {
+	hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// create access token and refresh token in `family` and store refresh token in database
+async fn create_pair(db: &DatabaseWrapper, user: &User, family: ObjectId) -> Result<TokenPair> {
+	let (token, expires_at) = create_token_with_exp(db, user).await?;
+
+	let refresh_token = generate_token();
+	let refresh_expires_at = refresh_expire_time();
+	let record = RefreshToken::new(
+		*user.id_ref(),
+		family,
+		hash_token(&refresh_token),
+		DateTime::from_millis(refresh_expires_at as i64),
+	);
+	db.refresh_tokens().insert_one(&record, None).await?;
+
+	Ok(TokenPair {
+		token,
+		expires_at,
+		refresh_token,
+		refresh_expires_at: refresh_expires_at / 1000,
+	})
+}
+
+/// issue new token pair for user, this start new refresh token family (use after login)
+pub async fn issue_token_pair(db: impl Deref<Target=DatabaseWrapper>, user: &User) -> Result<TokenPair> {
+	create_pair(&db, user, ObjectId::new()).await
+}
+
+/// exchange refresh token to new token pair, old refresh token can't be used again
+///
+/// if already used token is presented again, it's likely stolen so every token in the family will be revoked
+pub async fn rotate_refresh_token(db: impl Deref<Target=DatabaseWrapper>, refresh_token: &str) -> Result<TokenPair, RefreshError> {
+	let repo = db.refresh_tokens();
+	let hash = hash_token(refresh_token);
+
+	let token = match repo.take_by_hash(&hash).await? {
+		Some(token) => token,
+		None => {
+			return match repo.find_by_hash(&hash).await? {
+				Some(token) if token.used && !token.revoked => {
+					log::warn!("refresh token reuse detected for user {}, revoking token family", token.user_id);
+					repo.revoke_family(&token.family).await?;
+					Err(RefreshError::Reused)
+				}
+				_ => Err(RefreshError::Invalid)
+			};
+		}
+	};
+
+	// mongodb remove expired document periodically, so it may still exist
+	if token.expire_at.timestamp_millis() as u64 <= timestamp_u64() {
+		return Err(RefreshError::Invalid);
+	}
+
+	// reload user so role changes are applied to new token
+	let result = match db.users().find_by_id(token.user_id).await {
+		Ok(Some(user)) => create_pair(&db, &user, token.family).await,
+		Ok(None) => return Err(RefreshError::Invalid),
+		Err(err) => Err(err.into()),
+	};
+	match result {
+		Ok(pair) => Ok(pair),
+		Err(err) => {
+			// new pair isn't issued, let client retry with same token instead of detecting it as reuse
+			if let Err(err) = repo.release_by_hash(&hash).await {
+				log::error!("failed to release refresh token: {:?}", err);
+			}
+			Err(RefreshError::Internal(err))
+		}
+	}
+}
+
+/// revoke family of refresh token if it belongs to `user_id` (use on logout)
+pub async fn revoke_refresh_token(db: impl Deref<Target=DatabaseWrapper>, refresh_token: &str, user_id: &ObjectId) -> Result<()> {
+	let repo = db.refresh_tokens();
+	if let Some(token) = repo.find_by_hash(&hash_token(refresh_token)).await? {
+		if &token.user_id == user_id {
+			repo.revoke_family(&token.family).await?;
+		}
+	}
+	Ok(())
+}
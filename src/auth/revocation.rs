@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::RwLock;
+
+use anyhow::Result;
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+
+use crate::config;
+use crate::manager::DatabaseWrapper;
+use crate::schema::{Jwt, RevokedToken};
+use crate::util::time::timestamp_u64;
+
+use super::middleware::jwt_expire_time;
+
+/// cleanup expired cache entry once cache grow beyond this size
+const REVOCATION_CACHE_CLEANUP_SIZE: usize = 10_000;
+
+/// cached result of revocation lookup
+#[derive(Clone, Copy)]
+struct CacheEntry {
+	/// entry exist in database
+	revoked: bool,
+	/// copied from [RevokedToken::revoked_before]
+	revoked_before: Option<u64>,
+	/// entry can be used until this timestamp
+	valid_until: u64,
+}
+
+lazy_static::lazy_static! {
+	static ref CACHE: RwLock<HashMap<String, CacheEntry>> = RwLock::new(HashMap::new());
+	/// time to trust cached lookup (other instance may revoke token in the meantime)
+	static ref CACHE_TTL: u64 = config::get().auth.revocation_cache * 1000;
+}
+
+/// put entry into in-process cache
+fn cache_put(key: String, entry: CacheEntry) {
+	let mut cache = CACHE.write().unwrap();
+	if cache.len() >= REVOCATION_CACHE_CLEANUP_SIZE {
+		let now = timestamp_u64();
+		cache.retain(|_, it| it.valid_until > now);
+	}
+	cache.insert(key, entry);
+}
+
+/// get entry from in-process cache if it's still valid
+fn cache_get(key: &str, now: u64) -> Option<CacheEntry> {
+	CACHE.read().unwrap().get(key).copied().filter(|it| it.valid_until > now)
+}
+
+/// check if token has been revoked (by logout or log out everywhere)
+///
+/// "log out everywhere" revoke every token issued before it (millisecond precision)
+pub async fn is_revoked(db: impl Deref<Target=DatabaseWrapper>, jwt: &Jwt) -> Result<bool> {
+	let now = timestamp_u64();
+	let keys = [jwt.jti.clone(), RevokedToken::user_key(&jwt.sub)];
+
+	let mut entries = [cache_get(&keys[0], now), cache_get(&keys[1], now)];
+	let missing: Vec<&str> = keys.iter()
+		.zip(entries.iter())
+		.filter(|(_, entry)| entry.is_none())
+		.map(|(key, _)| key.as_str())
+		.collect();
+
+	if !missing.is_empty() {
+		let found = db.revoked_tokens().find_by_ids(&missing).await?;
+		for (key, entry) in keys.iter().zip(entries.iter_mut()) {
+			if entry.is_some() { continue; }
+			let record = found.iter().find(|it| &it._id == key);
+			let fetched = CacheEntry {
+				revoked: record.is_some(),
+				revoked_before: record.and_then(|it| it.revoked_before),
+				valid_until: now + *CACHE_TTL,
+			};
+			cache_put(key.clone(), fetched);
+			*entry = Some(fetched);
+		}
+	}
+
+	let token_revoked = entries[0].map(|it| it.revoked).unwrap_or_default();
+	let user_revoked = entries[1]
+		.and_then(|it| it.revoked_before)
+		.map(|before| jwt.issued_at_ms() < before)
+		.unwrap_or_default();
+	Ok(token_revoked || user_revoked)
+}
+
+/// revoke single token, it will be rejected until it's expired
+pub async fn revoke_token(db: impl Deref<Target=DatabaseWrapper>, jwt: &Jwt) -> Result<()> {
+	let record = RevokedToken::token(jwt.jti.clone(), DateTime::from_millis(jwt.exp as i64 * 1000));
+	db.revoked_tokens().upsert(&record).await?;
+	// token revocation never change, so it can be cached until token is expired
+	cache_put(jwt.jti.clone(), CacheEntry {
+		revoked: true,
+		revoked_before: None,
+		valid_until: jwt.exp * 1000,
+	});
+	Ok(())
+}
+
+/// revoke every token and refresh token of user issued until now (log out everywhere)
+pub async fn revoke_all(db: impl Deref<Target=DatabaseWrapper>, user_id: &ObjectId) -> Result<()> {
+	let now = timestamp_u64();
+	let user_id_str = user_id.to_string();
+	// every token issued before now will be expired before newly issued token
+	let record = RevokedToken::user(&user_id_str, now, DateTime::from_millis(jwt_expire_time() as i64 * 1000));
+	db.revoked_tokens().upsert(&record).await?;
+	db.refresh_tokens().revoke_user(user_id).await?;
+	cache_put(record._id, CacheEntry {
+		revoked: true,
+		revoked_before: Some(now),
+		valid_until: now + *CACHE_TTL,
+	});
+	Ok(())
+}
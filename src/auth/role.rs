@@ -0,0 +1,125 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+
+use actix_web::{dev, Error, FromRequest, HttpRequest};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use futures::future::{ready, Ready};
+
+use crate::schema::Jwt;
+use crate::web::error::forbidden;
+
+use super::guard::{ClaimsGuardMiddleware, ClaimsRequirement};
+
+/// role which can be required by [RequireRole]
+/// # Example
+/// ```rust
+/// use actix_mongo_jwt_web_template::auth::role::Role;
+/// pub struct Staff;
+/// impl Role for Staff {
+///     const NAME: &'static str = "staff";
+/// }
+/// ```
+pub trait Role {
+	/// name of role stored in user and jwt
+	const NAME: &'static str;
+}
+
+/// administrator role
+pub struct Admin;
+
+impl Role for Admin {
+	const NAME: &'static str = "admin";
+}
+
+/// extractor which require token having role `R`
+/// + 401 if token is missing, expired or invalid
+/// + 403 if token doesn't have role `R`
+/// # Example
+/// ```rust
+/// use actix_web::{delete, Responder};
+/// use actix_mongo_jwt_web_template::auth::role::{Admin, RequireRole};
+/// #[delete("/user/{id}")]
+/// async fn delete_user(jwt: RequireRole<Admin>) -> impl Responder { "" }
+/// ```
+pub struct RequireRole<R: Role> {
+	jwt: Jwt,
+	_role: PhantomData<fn() -> R>,
+}
+
+impl<R: Role> RequireRole<R> {
+	/// get claims of token
+	pub fn into_inner(self) -> Jwt {
+		self.jwt
+	}
+}
+
+impl<R: Role> Deref for RequireRole<R> {
+	type Target = Jwt;
+
+	fn deref(&self) -> &Self::Target {
+		&self.jwt
+	}
+}
+
+impl<R: Role + 'static> FromRequest for RequireRole<R> {
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self, Error>>>>;
+
+	fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+		let jwt = Jwt::from_request(req, payload);
+		Box::pin(async move {
+			let jwt = jwt.await?;
+			if jwt.has_role(R::NAME) {
+				Ok(Self { jwt, _role: PhantomData })
+			} else {
+				Err(forbidden())
+			}
+		})
+	}
+}
+
+/// middleware which require token having at least one of `roles` for every route it wraps,
+/// empty `roles` allow every request
+/// # Example
+/// ```rust
+/// use actix_web::web;
+/// use actix_mongo_jwt_web_template::auth::role::RequireRoles;
+/// web::scope("admin").wrap(RequireRoles::any(&["admin"]));
+/// ```
+#[derive(Clone, Copy)]
+pub struct RequireRoles {
+	roles: &'static [&'static str],
+}
+
+impl RequireRoles {
+	/// require at least one of `roles`
+	pub fn any(roles: &'static [&'static str]) -> Self {
+		Self { roles }
+	}
+}
+
+impl ClaimsRequirement for RequireRoles {
+	fn is_empty(&self) -> bool {
+		self.roles.is_empty()
+	}
+
+	fn check(&self, jwt: &Jwt) -> bool {
+		self.roles.iter().any(|role| jwt.has_role(role))
+	}
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRoles
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Transform = ClaimsGuardMiddleware<S, Self>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(ClaimsGuardMiddleware::new(service, *self)))
+	}
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::util::env::env;
+
+/// directory to look for config file when `APP_CONFIG_DIR` is not set
+const CONFIG_DIR: &str = "config";
+/// supported config file, first one found is used
+const EXTENSIONS: &[&str] = &["toml", "yaml", "yml"];
+/// file always loaded before `APP_ENV` file
+const DEFAULT_NAME: &str = "default";
+
+/// load `default` file then `$APP_ENV` file from `$APP_CONFIG_DIR`, later file override earlier one
+///
+/// every key is flattened into env var name so env var can override it,
+/// e.g. `jwt_secret` in `[auth]` table become `AUTH_JWT_SECRET`
+pub(super) fn load(errors: &mut Vec<String>) -> HashMap<String, String> {
+	let dir = PathBuf::from(env("APP_CONFIG_DIR").unwrap_or_else(|| CONFIG_DIR.to_string()));
+	load_dir(&dir, env("APP_ENV").as_deref(), errors)
+}
+
+/// load `default` file then `app_env` file from `dir`
+fn load_dir(dir: &Path, app_env: Option<&str>, errors: &mut Vec<String>) -> HashMap<String, String> {
+	let mut values = HashMap::new();
+
+	load_layer(dir, DEFAULT_NAME, &mut values, errors);
+	if let Some(app_env) = app_env {
+		if !load_layer(dir, app_env, &mut values, errors) {
+			log::warn!("no config file for `APP_ENV={}` in {}", app_env, dir.display());
+		}
+	}
+	values
+}
+
+/// load `{dir}/{name}.{ext}` into `values`, return false if there is no such file
+fn load_layer(dir: &Path, name: &str, values: &mut HashMap<String, String>, errors: &mut Vec<String>) -> bool {
+	let path = match EXTENSIONS.iter().map(|ext| dir.join(format!("{}.{}", name, ext))).find(|it| it.is_file()) {
+		Some(path) => path,
+		None => return false
+	};
+	match parse(&path) {
+		Ok(Value::Object(table)) => {
+			log::info!("loaded config from {}", path.display());
+			for (key, value) in table {
+				flatten(env_key("", &key), value, values);
+			}
+		}
+		Ok(_) => errors.push(format!("config file {} must contain a table", path.display())),
+		Err(err) => errors.push(format!("failed to load config file {}: {}", path.display(), err)),
+	}
+	true
+}
+
+fn parse(path: &Path) -> Result<Value, String> {
+	let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+	match path.extension().and_then(|it| it.to_str()) {
+		Some("toml") => toml::from_str(&content).map_err(|err| err.to_string()),
+		_ => serde_yaml::from_str(&content).map_err(|err| err.to_string()),
+	}
+}
+
+/// `auth` + `jwt-secret` -> `AUTH_JWT_SECRET`
+fn env_key(prefix: &str, key: &str) -> String {
+	let key = key.to_ascii_uppercase().replace('-', "_");
+	if prefix.is_empty() { key } else { format!("{}_{}", prefix, key) }
+}
+
+/// nested table is joined with `_`, array is joined with `,` (same as env var)
+fn flatten(key: String, value: Value, values: &mut HashMap<String, String>) {
+	match value {
+		Value::Object(table) => {
+			for (child, value) in table {
+				flatten(env_key(&key, &child), value, values);
+			}
+		}
+		Value::Array(items) => {
+			let items: Vec<String> = items.into_iter().filter_map(scalar).collect();
+			values.insert(key, items.join(","));
+		}
+		value => {
+			if let Some(value) = scalar(value) {
+				values.insert(key, value);
+			}
+		}
+	}
+}
+
+fn scalar(value: Value) -> Option<String> {
+	match value {
+		Value::Null => None,
+		Value::String(value) => Some(value),
+		value => Some(value.to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// empty directory unique to the test
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("config-file-{}-{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn env_key_is_upper_snake_case() {
+		assert_eq!(env_key("", "jwt-secret"), "JWT_SECRET");
+		assert_eq!(env_key("AUTH", "jwt_secret"), "AUTH_JWT_SECRET");
+	}
+
+	#[test]
+	fn flatten_nested_table_and_array() {
+		let value: Value = toml::from_str(r#"
+			[http]
+			bind = "127.0.0.1:8008"
+			keep_alive = 30
+			cors_hosts = ["http://a", "http://b"]
+			[http.tls]
+			redirect = true
+		"#).unwrap();
+		let mut values = HashMap::new();
+		flatten(String::new(), value, &mut values);
+		assert_eq!(values["HTTP_BIND"], "127.0.0.1:8008");
+		assert_eq!(values["HTTP_KEEP_ALIVE"], "30");
+		assert_eq!(values["HTTP_CORS_HOSTS"], "http://a,http://b");
+		assert_eq!(values["HTTP_TLS_REDIRECT"], "true");
+	}
+
+	#[test]
+	fn app_env_file_override_default_file() {
+		let dir = temp_dir("layer");
+		std::fs::write(dir.join("default.toml"), "[http]\nbind = \"127.0.0.1:8008\"\ncors_hosts = [\"http://a\"]\n").unwrap();
+		std::fs::write(dir.join("production.yaml"), "http:\n  bind: 0.0.0.0:8008\n").unwrap();
+		let mut errors = Vec::new();
+
+		let values = load_dir(&dir, Some("production"), &mut errors);
+		assert_eq!(values["HTTP_BIND"], "0.0.0.0:8008");
+		assert_eq!(values["HTTP_CORS_HOSTS"], "http://a");
+
+		let values = load_dir(&dir, None, &mut errors);
+		assert_eq!(values["HTTP_BIND"], "127.0.0.1:8008");
+		// missing `APP_ENV` file is only a warning
+		let values = load_dir(&dir, Some("staging"), &mut errors);
+		assert_eq!(values["HTTP_BIND"], "127.0.0.1:8008");
+		assert!(errors.is_empty());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn invalid_file_is_reported() {
+		let dir = temp_dir("invalid");
+		std::fs::write(dir.join("default.toml"), "[http\n").unwrap();
+		let mut errors = Vec::new();
+		load_dir(&dir, None, &mut errors);
+		assert_eq!(errors.len(), 1);
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
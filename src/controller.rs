@@ -1,11 +1,55 @@
+use actix_web::dev::HttpServiceFactory;
 use actix_web::Scope;
 
+use crate::auth::permission::RequirePermissions;
+use crate::auth::role::RequireRoles;
+
 /// contains routing to authentication
 pub mod auth_controller;
 pub use auth_controller::AuthController;
 
+/// contains routing to well-known uri like jwks
+pub mod well_known_controller;
+pub use well_known_controller::WellKnownController;
+
+/// contains routing to health probe
+pub mod health_controller;
+pub use health_controller::HealthController;
+
+/// contains routing to prometheus metrics
+pub mod metrics_controller;
+pub use metrics_controller::MetricsController;
+
 /// Base function for controller
 pub trait Controller {
 	/// this function use to create routing to the controller
 	fn create_scope() -> Scope;
-}
\ No newline at end of file
+
+	/// roles required to access every route in this controller, user need at least one of them
+	/// (default: empty, no requirement)
+	///
+	/// requirement for some routes can be declared in [Controller::create_scope] by nesting scope
+	/// ```rust
+	/// use actix_web::web;
+	/// use actix_mongo_jwt_web_template::auth::role::RequireRoles;
+	/// web::scope("users")
+	///     .service(web::scope("").wrap(RequireRoles::any(&["admin"])));
+	/// ```
+	fn required_roles() -> &'static [&'static str] {
+		&[]
+	}
+
+	/// permissions required to access every route in this controller, user need all of them
+	/// (default: empty, no requirement)
+	fn required_permissions() -> &'static [&'static str] {
+		&[]
+	}
+
+	/// create scope with [Controller::required_roles] and [Controller::required_permissions] applied,
+	/// use this to register controller to `App`
+	fn create_service() -> impl HttpServiceFactory {
+		Self::create_scope()
+			.wrap(RequirePermissions::all(Self::required_permissions()))
+			.wrap(RequireRoles::any(Self::required_roles()))
+	}
+}
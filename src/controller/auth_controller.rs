@@ -1,22 +1,39 @@
 use actix_web::{get, HttpResponse, post, Responder, Scope, web};
 use actix_web::web::Json;
+use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
+use crate::auth::{register_enabled, register_user};
 use crate::auth::middleware::login_as_token;
+use crate::auth::refresh::{issue_token_pair, revoke_refresh_token, rotate_refresh_token, TokenPair};
+use crate::auth::revocation::{revoke_all, revoke_token};
 use crate::controller::Controller;
 use crate::manager::database::DatabaseRef;
 use crate::schema::Jwt;
+use crate::web::error::{ApiError, ApiStatus};
 
 /// this controller contains routing for authentication
 pub struct AuthController;
 
 impl Controller for AuthController {
 	fn create_scope() -> Scope {
-		web::scope("auth")
+		let scope = web::scope("auth")
 			// route to /auth/login
 			.service(login)
+			// route to /auth/refresh
+			.service(refresh)
+			// route to /auth/logout
+			.service(logout)
+			// route to /auth/logout/all
+			.service(logout_all)
 			// route to /auth/check
-			.service(check)
+			.service(check);
+		if register_enabled() {
+			// route to /auth/register
+			scope.service(register)
+		} else {
+			scope
+		}
 	}
 }
 
@@ -28,10 +45,32 @@ struct LoginData {
 	password: String,
 }
 
+/// use to receive refresh token from client
+#[derive(Deserialize)]
+struct RefreshData {
+	refresh_token: String,
+}
+
 /// use to response token to client
 #[derive(Serialize)]
 struct LoginResponse {
 	token: String,
+	/// access token expire timestamp (seconds)
+	expires_at: u64,
+	refresh_token: String,
+	/// refresh token expire timestamp (seconds)
+	refresh_expires_at: u64,
+}
+
+impl From<TokenPair> for LoginResponse {
+	fn from(pair: TokenPair) -> Self {
+		Self {
+			token: pair.token,
+			expires_at: pair.expires_at,
+			refresh_token: pair.refresh_token,
+			refresh_expires_at: pair.refresh_expires_at,
+		}
+	}
 }
 
 /// this route will take username and password from request and response token back
@@ -43,14 +82,90 @@ struct LoginResponse {
 /// {"username":"username","password":"password"}
 /// ```
 /// ## Response
-/// + 200 `{"token":"..jwt..token.."}`
-/// + 401 if failed to verify username or password
+/// + 200 `{"token":"..jwt..token..","expires_at":0,"refresh_token":"..","refresh_expires_at":0}`
+/// + 401 `{"ok":false,"error":"Invalid username or password!","code":"invalid_credentials"}` if failed to verify username or password
+/// + 500 `internal_error` if database or signing failed
 #[post("/login")]
-async fn login(Json(LoginData { username, password }): Json<LoginData>, db: DatabaseRef) -> impl Responder {
-	if let Ok(token) = login_as_token(db.get_ref(), username.as_str(), password.as_str()).await {
-		return HttpResponse::Ok().json(LoginResponse { token });
+async fn login(Json(LoginData { username, password }): Json<LoginData>, db: DatabaseRef) -> Result<HttpResponse, ApiError> {
+	let pair = login_as_token(db.get_ref(), username.as_str(), password.as_str()).await?;
+	Ok(HttpResponse::Ok().json(LoginResponse::from(pair)))
+}
+
+/// this route will create new user from username and password and response token back
+/// (can be disabled by setting `AUTH_REGISTER_ENABLE=0`)
+/// ## Request
+/// ```http
+/// POST /auth/register
+/// Content-Type: application/json
+///
+/// {"username":"username","password":"password"}
+/// ```
+/// ## Response
+/// + 201 `{"token":"..jwt..token..","expires_at":0,"refresh_token":"..","refresh_expires_at":0}`
+/// + 400 `bad_request` if username or password doesn't meet requirement
+/// + 409 `conflict` if username is already taken
+#[post("/register")]
+async fn register(Json(LoginData { username, password }): Json<LoginData>, db: DatabaseRef) -> Result<HttpResponse, ApiError> {
+	let user = register_user(db.get_ref(), username.as_str(), password.as_str()).await?;
+	let pair = issue_token_pair(db.get_ref(), &user).await?;
+	Ok(HttpResponse::Created().json(LoginResponse::from(pair)))
+}
+
+/// this route will exchange refresh token to new token pair, refresh token can only be used once
+/// ## Request
+/// ```http
+/// POST /auth/refresh
+/// Content-Type: application/json
+///
+/// {"refresh_token":"..refresh..token.."}
+/// ```
+/// ## Response
+/// + 200 `{"token":"..jwt..token..","expires_at":0,"refresh_token":"..","refresh_expires_at":0}`
+/// + 401 `invalid_refresh_token` if refresh token is invalid or expired
+/// + 401 `revoked_refresh_token` if it's already used (reusing token revoke all token from the same login)
+#[post("/refresh")]
+async fn refresh(Json(RefreshData { refresh_token }): Json<RefreshData>, db: DatabaseRef) -> Result<HttpResponse, ApiError> {
+	let pair = rotate_refresh_token(db.get_ref(), refresh_token.as_str()).await?;
+	Ok(HttpResponse::Ok().json(LoginResponse::from(pair)))
+}
+
+/// this route will revoke current token (and refresh token if provided)
+/// ## Request
+/// ```http
+/// POST /auth/logout
+/// Authorization: Bearer "jwt..token"
+/// Content-Type: application/json
+///
+/// {"refresh_token":"..refresh..token.."}
+/// ```
+/// body is optional
+/// ## Response
+/// + 200 `{"ok":true}`
+/// + 401 if token is expired or invalid
+#[post("/logout")]
+async fn logout(jwt: Jwt, body: Option<Json<RefreshData>>, db: DatabaseRef) -> Result<HttpResponse, ApiError> {
+	revoke_token(db.get_ref(), &jwt).await?;
+	if let Some(Json(RefreshData { refresh_token })) = body {
+		let user_id = ObjectId::parse_str(&jwt.sub).map_err(|_| ApiError::InvalidToken)?;
+		revoke_refresh_token(db.get_ref(), refresh_token.as_str(), &user_id).await?;
 	}
-	HttpResponse::Unauthorized().finish()
+	Ok(HttpResponse::Ok().json(ApiStatus::ok()))
+}
+
+/// this route will revoke every token and refresh token of current user (log out everywhere)
+/// ## Request
+/// ```http
+/// POST /auth/logout/all
+/// Authorization: Bearer "jwt..token"
+/// ```
+/// ## Response
+/// + 200 `{"ok":true}`
+/// + 401 if token is expired or invalid
+#[post("/logout/all")]
+async fn logout_all(jwt: Jwt, db: DatabaseRef) -> Result<HttpResponse, ApiError> {
+	let user_id = ObjectId::parse_str(&jwt.sub).map_err(|_| ApiError::InvalidToken)?;
+	revoke_all(db.get_ref(), &user_id).await?;
+	Ok(HttpResponse::Ok().json(ApiStatus::ok()))
 }
 
 /// this route use to check token (have nothing because it already handles in jwt)
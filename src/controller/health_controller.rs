@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use actix_web::{get, HttpResponse, Responder, Scope, web};
+use actix_web::http::StatusCode;
+use mongodb::bson::doc;
+use serde::Serialize;
+
+use crate::controller::Controller;
+use crate::manager::database::{DatabaseRef, is_preloaded};
+use crate::manager::DatabaseWrapper;
+use crate::manager::shutdown::is_shutting_down;
+
+/// database is considered unreachable if ping take longer than this
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// this controller contains routing for load balancer / orchestrator probe
+pub struct HealthController;
+
+impl Controller for HealthController {
+	fn create_scope() -> Scope {
+		web::scope("health")
+			// route to /health/live
+			.service(live)
+			// route to /health/ready
+			.service(ready)
+	}
+}
+
+/// status of service or single dependency
+#[derive(Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+	Up,
+	Down,
+}
+
+/// status of single dependency
+#[derive(Serialize)]
+struct Check {
+	status: Status,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	latency_ms: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+	status: Status,
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	checks: BTreeMap<&'static str, Check>,
+}
+
+/// this route tell that process is running and able to handle request
+/// ## Request
+/// ```http
+/// GET /health/live
+/// ```
+/// ## Response
+/// + 200 `{"status":"up"}`
+#[get("/live")]
+async fn live() -> impl Responder {
+	HttpResponse::Ok().json(HealthResponse { status: Status::Up, checks: BTreeMap::new() })
+}
+
+/// this route tell that service is ready to receive traffic
+/// ## Request
+/// ```http
+/// GET /health/ready
+/// ```
+/// ## Response
+/// + 200 `{"status":"up","checks":{"database":{"status":"up","latency_ms":1},"migrations":{"status":"up"}}}`
+/// + 503 if database is unreachable or migration is still running
+///   `{"status":"down","checks":{"database":{"status":"down","latency_ms":2000,"error":"timeout"},..}}`
+/// + 503 once shutdown is requested, database isn't checked anymore
+///   `{"status":"down","checks":{"shutdown":{"status":"down","error":"draining"}}}`
+#[get("/ready")]
+async fn ready(db: DatabaseRef) -> impl Responder {
+	let mut checks = BTreeMap::new();
+	if is_shutting_down() {
+		checks.insert("shutdown", Check { status: Status::Down, latency_ms: None, error: Some("draining") });
+		return HttpResponse::ServiceUnavailable().json(HealthResponse { status: Status::Down, checks });
+	}
+	checks.insert("database", ping(&db).await);
+	checks.insert("migrations", if is_preloaded() {
+		Check { status: Status::Up, latency_ms: None, error: None }
+	} else {
+		Check { status: Status::Down, latency_ms: None, error: Some("running") }
+	});
+
+	let (code, status) = if checks.values().all(|it| it.status == Status::Up) {
+		(StatusCode::OK, Status::Up)
+	} else {
+		(StatusCode::SERVICE_UNAVAILABLE, Status::Down)
+	};
+	HttpResponse::build(code).json(HealthResponse { status, checks })
+}
+
+/// send `ping` command to database, error detail is logged instead of exposed
+async fn ping(db: &DatabaseWrapper) -> Check {
+	let start = Instant::now();
+	let result = actix_rt::time::timeout(PING_TIMEOUT, db.run_command(doc! {"ping":1}, None)).await;
+	let latency_ms = Some(start.elapsed().as_millis() as u64);
+	match result {
+		Ok(Ok(_)) => Check { status: Status::Up, latency_ms, error: None },
+		Ok(Err(err)) => {
+			log::warn!("database ping failed: {}", err);
+			Check { status: Status::Down, latency_ms, error: Some("unreachable") }
+		}
+		Err(_) => Check { status: Status::Down, latency_ms, error: Some("timeout") },
+	}
+}
@@ -0,0 +1,45 @@
+use actix_web::{HttpRequest, HttpResponse, Scope, web};
+use actix_web::http::header::AUTHORIZATION;
+use sha2::{Digest, Sha256};
+
+use crate::config;
+use crate::controller::Controller;
+use crate::metrics::render;
+use crate::web::error::ApiError;
+
+/// this controller expose metrics to prometheus
+pub struct MetricsController;
+
+impl Controller for MetricsController {
+	fn create_scope() -> Scope {
+		web::scope("metrics")
+			// route to /metrics
+			.route("", web::get().to(metrics))
+	}
+}
+
+/// this route response every metric in prometheus text format,
+/// bearer token is required when `HTTP_METRICS_TOKEN` is set
+/// ## Request
+/// ```http
+/// GET /metrics
+/// Authorization: Bearer "metrics token"
+/// ```
+/// ## Response
+/// + 200 `http_requests_total{method="POST",route="/auth/login",status="200"} 1`
+/// + 401 `{"ok":false,"error":"Missing token!","code":"missing_token"}`
+async fn metrics(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+	if let Some(expected) = &config::get().http.metrics_token {
+		let token = req.headers().get(AUTHORIZATION)
+			.and_then(|it| it.to_str().ok())
+			.and_then(|it| it.strip_prefix("Bearer "))
+			.ok_or(ApiError::MissingToken)?;
+		// compare digest so time taken doesn't tell how much of token is right
+		if Sha256::digest(token.trim().as_bytes()) != Sha256::digest(expected) {
+			return Err(ApiError::InvalidToken);
+		}
+	}
+	Ok(HttpResponse::Ok()
+		.content_type("text/plain; version=0.0.4")
+		.body(render()))
+}
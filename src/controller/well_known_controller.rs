@@ -0,0 +1,28 @@
+use actix_web::{get, HttpResponse, Responder, Scope, web};
+
+use crate::auth::middleware::key_ring;
+use crate::controller::Controller;
+
+/// this controller contains routing for well-known uri (RFC 8615)
+pub struct WellKnownController;
+
+impl Controller for WellKnownController {
+	fn create_scope() -> Scope {
+		web::scope(".well-known")
+			// route to /.well-known/jwks.json
+			.service(jwks)
+	}
+}
+
+/// this route response public key used to sign jwt, so other service can verify token without secret
+/// ## Request
+/// ```http
+/// GET /.well-known/jwks.json
+/// ```
+/// ## Response
+/// + 200 `{"keys":[{"kty":"RSA","use":"sig","alg":"RS256","n":"..","e":"AQAB"}]}`
+///   (`keys` is empty when HMAC algorithm is used)
+#[get("/jwks.json")]
+async fn jwks() -> impl Responder {
+	HttpResponse::Ok().json(key_ring().jwks())
+}
@@ -2,12 +2,19 @@
 //! this template is personal template used in private project
 
 #![warn(missing_docs)]
-#![forbid(unused_imports)]
+// `deny` instead of `forbid`: span / event macro with field (`info_span!("request", request_id = ..)`,
+// `#[tracing::instrument]` recording argument) expand `tracing::valueset!` which contains
+// `#[allow(unused_imports)] use tracing::field::{debug, display, Value};`, and allow under forbid is error E0453.
+// No import of this crate need the allow, `deny` still reject every unused import of ours
+#![deny(unused_imports)]
 #![forbid(unsafe_code)]
 
 /// this module contains utilities and helpers
 pub mod util;
 
+/// this module contains typed application configuration
+pub mod config;
+
 /// this module contains stuff to make authorization
 pub mod auth;
 
@@ -24,4 +31,10 @@ pub mod web;
 pub mod repository;
 
 /// this module store configuration or manage server resources
-pub mod manager;
\ No newline at end of file
+pub mod manager;
+
+/// this module contains database migrations
+pub mod migration;
+
+/// this module contains prometheus metrics
+pub mod metrics;
\ No newline at end of file
@@ -1,12 +1,25 @@
 use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::Result;
 use mongodb::Database;
 use mongodb::options::ClientOptions;
 
-use crate::util::env::env;
+use crate::config::DatabaseConfig;
+use crate::metrics::CommandMetrics;
 
-use super::super::repository::{user_repo, UserRepository};
+use super::super::repository::REPOSITORY_INITS;
+use super::migration::migrate;
+use super::shutdown::{on_shutdown, Stage};
+
+/// set once [preload] is done
+static PRELOADED: AtomicBool = AtomicBool::new(false);
+
+/// check if index is created and migration is applied
+pub fn is_preloaded() -> bool {
+	PRELOADED.load(Ordering::Acquire)
+}
 
 /// use to extract database in route handler
 pub type DatabaseRef = actix_web::web::Data<DatabaseWrapper>;
@@ -16,12 +29,7 @@ pub type DatabaseRef = actix_web::web::Data<DatabaseWrapper>;
 #[repr(transparent)]
 pub struct DatabaseWrapper(pub Database);
 
-impl DatabaseWrapper {
-	/// get user controller with pre-configured collection
-	pub fn users(&self) -> UserRepository {
-		self.into()
-	}
-}
+// repository accessor like `users()` is generated by `#[derive(Repository)]`
 
 impl Deref for DatabaseWrapper {
 	type Target = Database;
@@ -31,22 +39,38 @@ impl Deref for DatabaseWrapper {
 	}
 }
 
-/// this function will load config from env and connect to database
-pub async fn init_database() -> Result<DatabaseWrapper> {
-	let db_url = env("DB_URL").expect("DB_URL must be set in .env");
-	let db_name = env("DB_NAME").expect("DB_NAME must be set in .env");
-	let options = ClientOptions::parse(&db_url).await?;
-	let client = mongodb::Client::with_options(options)?;
-	let db = DatabaseWrapper(client.database(db_name.as_str()));
-	preload(&db).await?;
+/// this function will connect to database using config
+pub async fn init_database(config: &DatabaseConfig) -> Result<DatabaseWrapper> {
+	let db = connect_database(config).await?;
+	preload(&db, config).await?;
 	Ok(db)
 }
 
+/// connect to database without creating index or migrating,
+/// connection is closed by shutdown hook ([Stage::Storage])
+pub async fn connect_database(config: &DatabaseConfig) -> Result<DatabaseWrapper> {
+	let mut options = ClientOptions::parse(&config.url).await?;
+	options.command_event_handler = Some(Arc::new(CommandMetrics));
+	let client = mongodb::Client::with_options(options)?;
+	let shutdown_client = client.clone();
+	on_shutdown(Stage::Storage, "database", move || shutdown_client.shutdown());
+	Ok(DatabaseWrapper(client.database(config.name.as_str())))
+}
+
 /// this function will call after database connected can be used to
 /// + init data once application start
 /// + create(ensure) index
-async fn preload(db: &DatabaseWrapper) -> Result<()> {
+/// + apply pending migration (unless `DB_MIGRATE=0`)
+pub async fn preload(db: &DatabaseWrapper, config: &DatabaseConfig) -> Result<()> {
+	// index declared by `#[derive(Repository)]`
+	for repository in REPOSITORY_INITS {
+		log::debug!("ensure index of `{}`", repository.collection);
+		(repository.init)(db).await?;
+	}
+	if config.migrate {
+		migrate(db).await?;
+	}
 	// put initialize here
-	user_repo::init(&db).await?;
+	PRELOADED.store(true, Ordering::Release);
 	Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::future::{Either, select};
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+
+use crate::migration::MIGRATIONS;
+use crate::repository::Repository;
+use crate::schema::MigrationRecord;
+
+use super::DatabaseWrapper;
+
+/// lock is taken over by other instance once expired (in case instance crashed while migrating)
+const LOCK_TTL: Duration = Duration::from_secs(60);
+/// how often lock is extended while migrating, failed attempt is retried until lock expire
+const LOCK_HEARTBEAT: Duration = Duration::from_secs(15);
+/// how long to wait before trying to take lock again
+const LOCK_RETRY: Duration = Duration::from_secs(1);
+
+/// future returned from migration step
+pub type MigrationFuture<'a> = Pin<Box<dyn Future<Output=Result<()>> + 'a>>;
+
+/// single step of migration
+pub type MigrationStep = fn(&DatabaseWrapper) -> MigrationFuture<'_>;
+
+/// versioned migration, applied version is recorded in `_migrations` collection
+pub struct Migration {
+	/// version, must be unique and migrations are applied in ascending order
+	pub version: i64,
+	/// short description
+	pub name: &'static str,
+	/// apply migration
+	pub up: MigrationStep,
+	/// revert migration, migration can't be rolled back without it
+	pub down: Option<MigrationStep>,
+}
+
+/// state of migration known by application
+pub struct MigrationStatus {
+	/// migration
+	pub migration: &'static Migration,
+	/// time when it was applied, None if it's pending
+	pub applied_at: Option<DateTime>,
+}
+
+/// apply every pending migration in order and return applied versions
+///
+/// only one instance can migrate at once, others wait until lock is released
+pub async fn migrate(db: &DatabaseWrapper) -> Result<Vec<i64>> {
+	check_order()?;
+	with_lock(db, || async move {
+		let repo = db.migrations();
+		let applied: HashSet<i64> = repo.applied().await?.iter().map(MigrationRecord::version).collect();
+		if let Some(unknown) = applied.iter().find(|version| !MIGRATIONS.iter().any(|it| it.version == **version)) {
+			log::warn!("database has migration {} which is unknown to this version of application", unknown);
+		}
+
+		let mut done = Vec::new();
+		for migration in MIGRATIONS.iter().filter(|it| !applied.contains(&it.version)) {
+			log::info!("applying migration {} ({})", migration.version, migration.name);
+			(migration.up)(db).await
+				.with_context(|| format!("migration {} ({}) failed", migration.version, migration.name))?;
+			repo.insert(&MigrationRecord::new(migration.version, migration.name.to_string())).await?;
+			done.push(migration.version);
+		}
+		Ok(done)
+	}).await
+}
+
+/// revert every applied migration newer than `target` (newest first) and return reverted versions
+pub async fn rollback(db: &DatabaseWrapper, target: i64) -> Result<Vec<i64>> {
+	check_order()?;
+	with_lock(db, || async move {
+		let repo = db.migrations();
+		let applied: HashSet<i64> = repo.applied().await?.iter().map(MigrationRecord::version).collect();
+		let revert: Vec<&Migration> = MIGRATIONS.iter().rev()
+			.filter(|it| it.version > target && applied.contains(&it.version))
+			.collect();
+		// check before reverting anything so database isn't left half way
+		if let Some(migration) = revert.iter().find(|it| it.down.is_none()) {
+			bail!("migration {} ({}) can't be rolled back", migration.version, migration.name);
+		}
+
+		let mut done = Vec::new();
+		for migration in revert {
+			log::info!("reverting migration {} ({})", migration.version, migration.name);
+			if let Some(down) = migration.down {
+				down(db).await
+					.with_context(|| format!("reverting migration {} ({}) failed", migration.version, migration.name))?;
+			}
+			repo.delete_by_id(migration.version).await?;
+			done.push(migration.version);
+		}
+		Ok(done)
+	}).await
+}
+
+/// get every migration known by application with time it was applied
+pub async fn status(db: &DatabaseWrapper) -> Result<Vec<MigrationStatus>> {
+	let applied = db.migrations().applied().await?;
+	Ok(MIGRATIONS.iter().map(|migration| MigrationStatus {
+		migration,
+		applied_at: applied.iter().find(|it| it.version() == migration.version).map(MigrationRecord::applied_at),
+	}).collect())
+}
+
+/// versions must be strictly ascending so order of migration is clear
+fn check_order() -> Result<()> {
+	for pair in MIGRATIONS.windows(2) {
+		if pair[0].version >= pair[1].version {
+			bail!("migration {} must be declared before {}", pair[1].version, pair[0].version);
+		}
+	}
+	Ok(())
+}
+
+fn lock_expire_time() -> DateTime {
+	DateTime::from_millis(DateTime::now().timestamp_millis() + LOCK_TTL.as_millis() as i64)
+}
+
+/// keep extending lock while migration step is running (it may take longer than [LOCK_TTL]),
+/// only return once lock was taken by other instance
+async fn heartbeat(db: &DatabaseWrapper, owner: &ObjectId) -> anyhow::Error {
+	loop {
+		actix_rt::time::sleep(LOCK_HEARTBEAT).await;
+		match db.migrations().try_lock(owner, lock_expire_time()).await {
+			Ok(true) => {}
+			Ok(false) => return anyhow!("migration lock was taken by other instance"),
+			Err(err) => log::warn!("failed to extend migration lock: {:?}", err),
+		}
+	}
+}
+
+/// run `f` while holding migration lock, lock is always released afterward
+///
+/// `f` is cancelled if lock is lost so two instances never migrate at once
+async fn with_lock<T, F, Fut>(db: &DatabaseWrapper, f: F) -> Result<T>
+	where F: FnOnce() -> Fut, Fut: Future<Output=Result<T>> {
+	let repo = db.migrations();
+	let owner = ObjectId::new();
+	while !repo.try_lock(&owner, lock_expire_time()).await? {
+		log::info!("waiting for other instance to finish migration");
+		actix_rt::time::sleep(LOCK_RETRY).await;
+	}
+
+	let result = match select(Box::pin(f()), Box::pin(heartbeat(db, &owner))).await {
+		Either::Left((result, _)) => result,
+		Either::Right((err, _)) => Err(err),
+	};
+	if let Err(err) = repo.unlock(&owner).await {
+		log::error!("failed to release migration lock: {:?}", err);
+	}
+	result
+}
@@ -1,4 +1,13 @@
-pub use database::{DatabaseWrapper, init_database};
+pub use database::{connect_database, DatabaseWrapper, init_database};
 
 /// this module contains function to manage database
 pub mod database;
+
+/// this module contains versioned migration runner
+pub mod migration;
+
+/// this module contains shutdown state and ordered shutdown hooks
+pub mod shutdown;
+
+/// this module load tls certificate and reload it when renewed
+pub mod tls;
\ No newline at end of file
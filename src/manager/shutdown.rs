@@ -0,0 +1,68 @@
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+
+/// hook taking longer than this is abandoned so process can still exit
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// set once shutdown is requested
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// check if process is going to stop, `/health/ready` report 503 from then
+pub fn is_shutting_down() -> bool {
+	SHUTTING_DOWN.load(Ordering::Acquire)
+}
+
+/// mark process as shutting down, new traffic should go to other instance
+pub fn begin_shutdown() {
+	SHUTTING_DOWN.store(true, Ordering::Release);
+}
+
+/// when hook is run after http server stopped, earlier stage run first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+	/// flush background work and release resource like unix socket file
+	Application,
+	/// close database connection
+	Storage,
+	/// flush log / trace, run last so other hook can still be traced
+	Telemetry,
+}
+
+struct Hook {
+	stage: Stage,
+	name: &'static str,
+	run: Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>,
+}
+
+lazy_static::lazy_static! {
+	static ref HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+}
+
+/// register hook run by [run_shutdown_hooks],
+/// hooks of same stage run in registration order
+/// ```rust
+/// use actix_mongo_jwt_web_template::manager::shutdown::{on_shutdown, Stage};
+/// on_shutdown(Stage::Application, "cache", || async { /* flush cache */ });
+/// ```
+pub fn on_shutdown<F, Fut>(stage: Stage, name: &'static str, hook: F)
+	where F: FnOnce() -> Fut + Send + 'static,
+	      Fut: Future<Output=()> + Send + 'static {
+	HOOKS.lock().unwrap().push(Hook { stage, name, run: Box::new(move || Box::pin(hook())) });
+}
+
+/// run every registered hook once, ordered by [Stage]
+pub async fn run_shutdown_hooks() {
+	let mut hooks = std::mem::take(&mut *HOOKS.lock().unwrap());
+	// stable sort keep registration order inside stage
+	hooks.sort_by_key(|it| it.stage);
+	for hook in hooks {
+		log::debug!("run shutdown hook `{}` ({:?})", hook.name, hook.stage);
+		if actix_rt::time::timeout(HOOK_TIMEOUT, (hook.run)()).await.is_err() {
+			log::warn!("shutdown hook `{}` timed out", hook.name);
+		}
+	}
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail, Context, Result};
+use rustls::{RootCertStore, ServerConfig};
+use rustls::crypto::{CryptoProvider, ring};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::pki_types::pem::{self, PemObject};
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+
+use crate::config::TlsConfig;
+use crate::util::env::read_secret_file;
+
+/// how often certificate files are checked for renewal
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// crypto provider used for key and handshake, explicit so another crate enabling aws-lc-rs doesn't make it ambiguous
+fn provider() -> Arc<CryptoProvider> {
+	Arc::new(ring::default_provider())
+}
+
+/// load PEM certificate chain and private key (PKCS#8, PKCS#1 or SEC1)
+pub fn load_certified_key(cert: &Path, key: &Path) -> Result<CertifiedKey> {
+	let data = std::fs::read(cert).with_context(|| format!("failed to read {}", cert.display()))?;
+	let chain = CertificateDer::pem_slice_iter(&data)
+		.collect::<Result<Vec<_>, _>>()
+		.with_context(|| format!("invalid certificate {}", cert.display()))?;
+	if chain.is_empty() {
+		bail!("no certificate found in {}", cert.display());
+	}
+
+	let data = read_secret_file(key).with_context(|| format!("failed to read {}", key.display()))?;
+	let der = match PrivateKeyDer::from_pem_slice(&data) {
+		Ok(der) => der,
+		Err(pem::Error::NoItemsFound) => bail!("no private key found in {}", key.display()),
+		Err(err) => return Err(err).with_context(|| format!("invalid private key {}", key.display())),
+	};
+	let signing_key = provider().key_provider.load_private_key(der)
+		.map_err(|_| anyhow!("unsupported private key type in {}", key.display()))?;
+	Ok(CertifiedKey::new(chain, signing_key))
+}
+
+/// load PEM CA bundle used to verify client certificate
+pub fn load_client_ca(path: &Path) -> Result<RootCertStore> {
+	let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+	let mut roots = RootCertStore::empty();
+	for der in CertificateDer::pem_slice_iter(&data) {
+		let der = der.with_context(|| format!("invalid certificate {}", path.display()))?;
+		roots.add(der).with_context(|| format!("invalid certificate in {}", path.display()))?;
+	}
+	if roots.is_empty() {
+		bail!("no certificate found in {}", path.display());
+	}
+	Ok(roots)
+}
+
+/// latest modification time of certificate and key
+fn modified(config: &TlsConfig) -> Option<SystemTime> {
+	let cert = std::fs::metadata(&config.cert).and_then(|it| it.modified()).ok()?;
+	let key = std::fs::metadata(&config.key).and_then(|it| it.modified()).ok()?;
+	Some(cert.max(key))
+}
+
+/// certificate currently served with time it was modified on disk
+struct Loaded {
+	key: Arc<CertifiedKey>,
+	modified: Option<SystemTime>,
+}
+
+/// serve certificate of https listener, certificate can be swapped without restarting server
+pub struct CertResolver {
+	config: TlsConfig,
+	current: RwLock<Loaded>,
+}
+
+impl CertResolver {
+	/// load certificate from `HTTP_TLS_CERT` and `HTTP_TLS_KEY`
+	pub fn new(config: &TlsConfig) -> Result<Self> {
+		let modified = modified(config);
+		let key = load_certified_key(&config.cert, &config.key)?;
+		Ok(Self {
+			config: config.clone(),
+			current: RwLock::new(Loaded { key: Arc::new(key), modified }),
+		})
+	}
+
+	/// load certificate again, current one is kept if it failed to load
+	pub fn reload(&self) -> Result<()> {
+		let modified = modified(&self.config);
+		let key = load_certified_key(&self.config.cert, &self.config.key)?;
+		*self.current.write().unwrap() = Loaded { key: Arc::new(key), modified };
+		Ok(())
+	}
+
+	/// reload if certificate or key is modified since it was loaded, return true if it's reloaded
+	pub fn reload_if_modified(&self) -> Result<bool> {
+		let modified = modified(&self.config);
+		if modified.is_none() || modified == self.current.read().unwrap().modified {
+			return Ok(false);
+		}
+		self.reload()?;
+		Ok(true)
+	}
+
+	/// rustls config use this resolver, `h2` and `http/1.1` ALPN is added by actix
+	///
+	/// client certificate is verified against `HTTP_TLS_CLIENT_CA` which is only loaded here (not reloaded)
+	pub fn server_config(self: &Arc<Self>) -> Result<ServerConfig> {
+		let builder = ServerConfig::builder_with_provider(provider()).with_safe_default_protocol_versions()?;
+		let builder = match &self.config.client_ca {
+			Some(path) => {
+				let roots = load_client_ca(path)?;
+				let mut verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider());
+				if !self.config.client_cert_required {
+					verifier = verifier.allow_unauthenticated();
+				}
+				let verifier = verifier.build().with_context(|| format!("invalid client CA {}", path.display()))?;
+				builder.with_client_cert_verifier(verifier)
+			}
+			None => builder.with_no_client_auth(),
+		};
+		Ok(builder.with_cert_resolver(self.clone()))
+	}
+}
+
+impl fmt::Debug for CertResolver {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CertResolver").field("cert", &self.config.cert).finish_non_exhaustive()
+	}
+}
+
+impl ResolvesServerCert for CertResolver {
+	fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+		Some(self.current.read().unwrap().key.clone())
+	}
+}
+
+/// check certificate files periodically and reload renewed one (e.g. by certbot / cert-manager)
+pub async fn watch_certificate(resolver: Arc<CertResolver>) {
+	let mut interval = actix_rt::time::interval(RELOAD_CHECK_INTERVAL);
+	loop {
+		interval.tick().await;
+		match resolver.reload_if_modified() {
+			Ok(true) => log::info!("tls certificate reloaded"),
+			Ok(false) => {}
+			Err(err) => log::error!("failed to reload tls certificate: {:#}", err),
+		}
+	}
+}
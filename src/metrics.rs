@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+use mongodb::event::command::{CommandEventHandler, CommandFailedEvent, CommandSucceededEvent};
+use prometheus::{Encoder, Histogram, HistogramVec, IntCounterVec, TextEncoder};
+use prometheus::{register_histogram, register_histogram_vec, register_int_counter_vec};
+
+lazy_static::lazy_static! {
+	/// number of http request by method, route pattern and status
+	pub static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+		"http_requests_total", "Number of HTTP requests", &["method", "route", "status"]
+	).unwrap();
+	/// http request latency by method and route pattern
+	pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+		"http_request_duration_seconds", "HTTP request latency", &["method", "route"]
+	).unwrap();
+	/// number of login attempt by result (`success` / `failure`)
+	pub static ref LOGINS: IntCounterVec = register_int_counter_vec!(
+		"auth_logins_total", "Number of login attempts", &["result"]
+	).unwrap();
+	/// time used to verify password with bcrypt
+	pub static ref PASSWORD_VERIFY_DURATION: Histogram = register_histogram!(
+		"auth_password_verify_duration_seconds", "Time to verify password hash",
+		vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]
+	).unwrap();
+	/// mongodb command latency by command name and outcome (`success` / `failure`)
+	pub static ref DB_COMMAND_DURATION: HistogramVec = register_histogram_vec!(
+		"mongodb_command_duration_seconds", "MongoDB command latency", &["command", "outcome"]
+	).unwrap();
+}
+
+/// encode every registered metric in prometheus text format
+pub fn render() -> String {
+	let mut buffer = Vec::new();
+	if let Err(err) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+		log::error!("failed to encode metrics: {}", err);
+	}
+	String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// record login result
+pub fn record_login(success: bool) {
+	LOGINS.with_label_values(&[if success { "success" } else { "failure" }]).inc();
+}
+
+/// observe time since `start` in seconds
+pub fn observe_since(histogram: &Histogram, start: Instant) {
+	histogram.observe(start.elapsed().as_secs_f64());
+}
+
+/// record every command sent to mongodb, attach to `ClientOptions::command_event_handler`
+pub struct CommandMetrics;
+
+impl CommandEventHandler for CommandMetrics {
+	fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+		DB_COMMAND_DURATION.with_label_values(&[&event.command_name, "success"]).observe(event.duration.as_secs_f64());
+	}
+
+	fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+		DB_COMMAND_DURATION.with_label_values(&[&event.command_name, "failure"]).observe(event.duration.as_secs_f64());
+	}
+}
@@ -0,0 +1,11 @@
+use crate::manager::migration::Migration;
+
+/// create `admin` role with every permission
+mod v0001_admin_role;
+
+/// every migration of database, must be ordered by version
+///
+/// add new migration at the end and never change version of migration which has been released
+pub const MIGRATIONS: &[Migration] = &[
+	Migration { version: 1, name: "admin_role", up: v0001_admin_role::up, down: Some(v0001_admin_role::down) },
+];
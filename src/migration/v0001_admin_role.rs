@@ -0,0 +1,26 @@
+use mongodb::bson::doc;
+
+use crate::auth::role::{Admin, Role};
+use crate::manager::migration::MigrationFuture;
+use crate::manager::DatabaseWrapper;
+use crate::repository::Repository;
+use crate::schema::RolePermissions;
+
+/// grant every permission to `admin` role unless it's already configured
+pub fn up(db: &DatabaseWrapper) -> MigrationFuture<'_> {
+	Box::pin(async move {
+		let roles = db.roles();
+		if !roles.exists(doc! {"_id":Admin::NAME}).await? {
+			roles.upsert(&RolePermissions::new(Admin::NAME.to_string(), vec!["*".to_string()])).await?;
+		}
+		Ok(())
+	})
+}
+
+/// remove `admin` role
+pub fn down(db: &DatabaseWrapper) -> MigrationFuture<'_> {
+	Box::pin(async move {
+		db.roles().delete_by_id(Admin::NAME).await?;
+		Ok(())
+	})
+}
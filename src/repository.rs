@@ -1,20 +1,195 @@
+use std::fmt::{Display, Formatter};
 use std::future::Future;
 use std::ops::Deref;
 use std::pin::Pin;
 
+use futures::future::BoxFuture;
+use futures::TryStreamExt;
 use mongodb::{Collection, IndexModel};
-use mongodb::bson::{doc, Document};
-use mongodb::options::IndexOptions;
+use mongodb::bson::{Bson, doc, Document};
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+use mongodb::options::{CountOptions, FindOptions, IndexOptions};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::Instrument;
 
 use crate::manager::DatabaseWrapper;
 
+pub use actix_mongo_jwt_web_template_macros::Repository;
+
 /// this module contains user repository use to manage user from database
 pub mod user_repo;
 pub use user_repo::UserRepository;
 
+/// this module contains refresh token repository use to rotate / revoke refresh token
+pub mod refresh_token_repo;
+pub use refresh_token_repo::RefreshTokenRepository;
+
+/// this module contains revoked token repository use to reject jwt before it expired
+pub mod revoked_token_repo;
+pub use revoked_token_repo::RevokedTokenRepository;
+
+/// this module contains role repository use to resolve permissions of role
+pub mod role_repo;
+pub use role_repo::RoleRepository;
+
+/// this module contains migration repository use to record applied migration and lock migration
+pub mod migration_repo;
+pub use migration_repo::MigrationRepository;
+
+/// future returned from index initialization of repository
+pub type InitFuture<'a> = Pin<Box<dyn Future<Output=anyhow::Result<()>> + 'a>>;
+
+/// index initialization generated by `#[derive(Repository)]` as `INIT`, called after connected to database
+pub struct RepositoryInit {
+	/// name of collection
+	pub collection: &'static str,
+	/// create(ensure) index of collection
+	pub init: fn(&DatabaseWrapper) -> InitFuture<'_>,
+}
+
+/// every repository declared with `#[derive(Repository)]`, add new one here so its index is created at startup
+pub static REPOSITORY_INITS: &[RepositoryInit] = &[
+	UserRepository::INIT,
+	RefreshTokenRepository::INIT,
+	RevokedTokenRepository::INIT,
+	RoleRepository::INIT,
+	MigrationRepository::INIT,
+];
+
+/// mongodb error code for unique index violation
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// check if error is caused by unique index violation (E11000 duplicate key)
+pub fn is_duplicate_key(err: &Error) -> bool {
+	matches!(
+		err.kind.as_ref(),
+		ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY_CODE
+	)
+}
+
+/// error returned from [Repository] operation
+#[derive(Debug)]
+pub enum RepositoryError {
+	/// unique index violation (E11000 duplicate key)
+	DuplicateKey(Error),
+	/// any other database error
+	Database(Error),
+}
+
+impl From<Error> for RepositoryError {
+	fn from(err: Error) -> Self {
+		if is_duplicate_key(&err) {
+			RepositoryError::DuplicateKey(err)
+		} else {
+			RepositoryError::Database(err)
+		}
+	}
+}
+
+impl Display for RepositoryError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RepositoryError::DuplicateKey(err) => write!(f, "duplicate key: {}", err),
+			RepositoryError::Database(err) => write!(f, "database error: {}", err),
+		}
+	}
+}
+
+impl std::error::Error for RepositoryError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			RepositoryError::DuplicateKey(err) | RepositoryError::Database(err) => Some(err),
+		}
+	}
+}
+
+/// result of [Repository] operation
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+/// document which can be stored in [Repository]
+pub trait Entity: Serialize + DeserializeOwned + Unpin + Send + Sync {}
+
+impl<T: Serialize + DeserializeOwned + Unpin + Send + Sync> Entity for T {}
+
+/// span around database operation
+fn operation_span<T>(collection: &Collection<T>, operation: &'static str) -> tracing::Span {
+	tracing::debug_span!("repository", collection = collection.name(), operation)
+}
+
 /// base repository trait provide basic functional of database repository
 pub trait Repository<T, F: Deref<Target=DatabaseWrapper>>: From<F> + Deref<Target=Collection<T>>
 	where T: 'static {
+	/// find document by `_id` return None if not found
+	fn find_by_id(&self, id: impl Into<Bson>) -> BoxFuture<'static, RepositoryResult<Option<T>>> where T: Entity {
+		let collection = self.deref().clone();
+		let id = id.into();
+		let span = operation_span(&collection, "find_by_id");
+		Box::pin(async move {
+			Ok(collection.find_one(doc! {"_id":id}, None).await?)
+		}.instrument(span))
+	}
+
+	/// find every document matching `filter`
+	fn find_many(&self, filter: Document, option: impl Into<Option<FindOptions>>) -> BoxFuture<'static, RepositoryResult<Vec<T>>> where T: Entity {
+		let collection = self.deref().clone();
+		let option = option.into();
+		let span = operation_span(&collection, "find_many");
+		Box::pin(async move {
+			let cursor = collection.find(filter, option).await?;
+			Ok(cursor.try_collect().await?)
+		}.instrument(span))
+	}
+
+	/// insert document and return its `_id`,
+	/// [RepositoryError::DuplicateKey] is returned when unique index is violated
+	fn insert<'a>(&self, item: &'a T) -> BoxFuture<'a, RepositoryResult<Bson>> where T: Entity {
+		let collection = self.deref().clone();
+		let span = operation_span(&collection, "insert");
+		Box::pin(async move {
+			Ok(collection.insert_one(item, None).await?.inserted_id)
+		}.instrument(span))
+	}
+
+	/// apply `update` (e.g. `{"$set":{..}}`) to document by `_id`, return false if not found
+	fn update_by_id(&self, id: impl Into<Bson>, update: Document) -> BoxFuture<'static, RepositoryResult<bool>> where T: Entity {
+		let collection = self.deref().clone();
+		let id = id.into();
+		let span = operation_span(&collection, "update_by_id");
+		Box::pin(async move {
+			Ok(collection.update_one(doc! {"_id":id}, update, None).await?.matched_count > 0)
+		}.instrument(span))
+	}
+
+	/// delete document by `_id`, return false if not found
+	fn delete_by_id(&self, id: impl Into<Bson>) -> BoxFuture<'static, RepositoryResult<bool>> where T: Entity {
+		let collection = self.deref().clone();
+		let id = id.into();
+		let span = operation_span(&collection, "delete_by_id");
+		Box::pin(async move {
+			Ok(collection.delete_one(doc! {"_id":id}, None).await?.deleted_count > 0)
+		}.instrument(span))
+	}
+
+	/// check if any document match `filter`
+	fn exists(&self, filter: Document) -> BoxFuture<'static, RepositoryResult<bool>> where T: Entity {
+		let collection = self.deref().clone();
+		let span = operation_span(&collection, "exists");
+		Box::pin(async move {
+			let option = CountOptions::builder().limit(1).build();
+			Ok(collection.count_documents(filter, option).await? > 0)
+		}.instrument(span))
+	}
+
+	/// count document matching `filter`
+	fn count(&self, filter: Document) -> BoxFuture<'static, RepositoryResult<u64>> where T: Entity {
+		let collection = self.deref().clone();
+		let span = operation_span(&collection, "count");
+		Box::pin(async move {
+			Ok(collection.count_documents(filter, None).await?)
+		}.instrument(span))
+	}
+
 	/// ensure index of single field is created in collection
 	#[inline]
 	fn ensure_index_single(&self, field: impl AsRef<str>) -> Pin<Box<dyn Future<Output=anyhow::Result<()>>>> {
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::bson::oid::ObjectId;
+use mongodb::Collection;
+use mongodb::options::{FindOptions, UpdateOptions};
+
+use crate::repository::{is_duplicate_key, Repository};
+use crate::schema::MigrationRecord;
+
+/// `_id` of lock document stored along with migration record
+const LOCK_ID: &str = "lock";
+
+/// this struct is wrapper to `Collection<MigrationRecord>` should have function to help to manage migration
+#[derive(Repository)]
+#[repository(collection = "_migrations", accessor = "migrations")]
+#[repr(transparent)]
+pub struct MigrationRepository(pub Collection<MigrationRecord>);
+
+impl MigrationRepository {
+	/// every applied migration ordered by version (lock document is skipped)
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn applied(&self) -> Result<Vec<MigrationRecord>> {
+		let option = FindOptions::builder().sort(doc! {"_id":1}).build();
+		let cursor = self.0.find(doc! {"_id":{"$type":"number"}}, option).await?;
+		Ok(cursor.try_collect().await?)
+	}
+
+	/// try to take lock for `owner` until `expire_at`, return false if it's held by someone else
+	///
+	/// expired lock can be taken (owner crashed while migrating),
+	/// owner can call this again to extend the lock
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn try_lock(&self, owner: &ObjectId, expire_at: DateTime) -> Result<bool> {
+		let lock = self.0.clone_with_type::<Document>();
+		let option = UpdateOptions::builder().upsert(true).build();
+		let result = lock.update_one(
+			doc! {"_id":LOCK_ID, "$or":[{"owner":null}, {"owner":owner}, {"expire_at":{"$lt":DateTime::now()}}]},
+			doc! {"$set":{"owner":owner, "expire_at":expire_at}},
+			option,
+		).await;
+		match result {
+			Ok(_) => Ok(true),
+			// lock document exist but doesn't match filter, so upsert try to insert it again
+			Err(err) if is_duplicate_key(&err) => Ok(false),
+			Err(err) => Err(err.into()),
+		}
+	}
+
+	/// release lock if it's still held by `owner`
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn unlock(&self, owner: &ObjectId) -> Result<()> {
+		let lock = self.0.clone_with_type::<Document>();
+		lock.update_one(doc! {"_id":LOCK_ID, "owner":owner}, doc! {"$set":{"owner":null}}, None).await?;
+		Ok(())
+	}
+}
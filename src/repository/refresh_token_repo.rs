@@ -0,0 +1,62 @@
+use anyhow::Result;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use mongodb::Collection;
+
+use crate::repository::Repository;
+use crate::schema::RefreshToken;
+
+/// this struct is wrapper to `Collection<RefreshToken>` should have function to help to manage refresh token
+#[derive(Repository)]
+#[repository(collection = "refresh_tokens")]
+#[repository(index(field = "token_hash", unique))]
+#[repository(index(field = "family"))]
+// remove expired token automatically
+#[repository(index(field = "expire_at", expire_after = 0))]
+#[repr(transparent)]
+pub struct RefreshTokenRepository(pub Collection<RefreshToken>);
+
+impl RefreshTokenRepository {
+	/// find token by hash return None if not found
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn find_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+		Ok(self.0.find_one(doc! {"token_hash":token_hash}, None).await?)
+	}
+
+	/// mark token as used and return it, return None if token doesn't exist or already used / revoked
+	///
+	/// this is atomic so only one of concurrent request can exchange same token
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn take_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+		Ok(self.0.find_one_and_update(
+			doc! {"token_hash":token_hash, "used":false, "revoked":false},
+			doc! {"$set":{"used":true}},
+			None,
+		).await?)
+	}
+
+	/// undo [RefreshTokenRepository::take_by_hash] when new token can't be issued
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn release_by_hash(&self, token_hash: &str) -> Result<()> {
+		self.0.update_one(
+			doc! {"token_hash":token_hash, "used":true, "revoked":false},
+			doc! {"$set":{"used":false}},
+			None,
+		).await?;
+		Ok(())
+	}
+
+	/// revoke every token in family
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn revoke_family(&self, family: &ObjectId) -> Result<()> {
+		self.0.update_many(doc! {"family":family}, doc! {"$set":{"revoked":true}}, None).await?;
+		Ok(())
+	}
+
+	/// revoke every token of user
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn revoke_user(&self, user_id: &ObjectId) -> Result<()> {
+		self.0.update_many(doc! {"user_id":user_id}, doc! {"$set":{"revoked":true}}, None).await?;
+		Ok(())
+	}
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::Collection;
+use mongodb::options::ReplaceOptions;
+
+use crate::repository::Repository;
+use crate::schema::RevokedToken;
+
+/// this struct is wrapper to `Collection<RevokedToken>` should have function to help to manage revoked token
+#[derive(Repository)]
+#[repository(collection = "revoked_tokens")]
+// remove revocation once token is expired
+#[repository(index(field = "expire_at", expire_after = 0))]
+#[repr(transparent)]
+pub struct RevokedTokenRepository(pub Collection<RevokedToken>);
+
+impl RevokedTokenRepository {
+	/// insert or replace revocation
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn upsert(&self, revoked: &RevokedToken) -> Result<()> {
+		let option = ReplaceOptions::builder().upsert(true).build();
+		self.0.replace_one(doc! {"_id":&revoked._id}, revoked, option).await?;
+		Ok(())
+	}
+
+	/// find every revocation matching any of `ids`
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn find_by_ids(&self, ids: &[&str]) -> Result<Vec<RevokedToken>> {
+		let cursor = self.0.find(doc! {"_id":{"$in":ids}}, None).await?;
+		Ok(cursor.try_collect().await?)
+	}
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::Collection;
+use mongodb::options::ReplaceOptions;
+
+use crate::repository::Repository;
+use crate::schema::RolePermissions;
+
+/// this struct is wrapper to `Collection<RolePermissions>` should have function to help to manage role permissions
+#[derive(Repository)]
+#[repository(collection = "roles")]
+#[repr(transparent)]
+pub struct RoleRepository(pub Collection<RolePermissions>);
+
+impl RoleRepository {
+	/// find every role matching `names`, unknown role is ignored
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn find_by_names(&self, names: &[String]) -> Result<Vec<RolePermissions>> {
+		let cursor = self.0.find(doc! {"_id":{"$in":names}}, None).await?;
+		Ok(cursor.try_collect().await?)
+	}
+
+	/// create or replace permissions of role
+	#[tracing::instrument(level = "debug", skip_all)]
+	pub async fn upsert(&self, role: &RolePermissions) -> Result<()> {
+		let option = ReplaceOptions::builder().upsert(true).build();
+		self.0.replace_one(doc! {"_id":&role._id}, role, option).await?;
+		Ok(())
+	}
+}
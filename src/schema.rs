@@ -2,6 +2,20 @@
 pub mod user;
 /// Jwt schema use for authorization
 pub mod jwt;
+/// Refresh token schema use to renew jwt
+pub mod refresh_token;
+/// Revoked token schema use to reject jwt before it expired
+pub mod revoked_token;
+/// Role schema use to map role to permissions
+pub mod role;
+/// Permission format shared by user, role and jwt scope
+pub mod permission;
+/// Migration schema use to record applied migration
+pub mod migration;
 
 pub use user::User;
-pub use jwt::Jwt;
\ No newline at end of file
+pub use jwt::Jwt;
+pub use refresh_token::RefreshToken;
+pub use revoked_token::RevokedToken;
+pub use role::RolePermissions;
+pub use migration::MigrationRecord;
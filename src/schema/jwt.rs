@@ -1,8 +1,48 @@
 use serde::{Serialize, Deserialize};
 
-/// Claims for JWT
+use super::permission::permission_matches;
+
+/// Claims for JWT (RFC 7519), timestamps are in seconds
 #[derive(Serialize, Deserialize)]
 pub struct Jwt {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) iss: Option<String>,
 	pub(crate) sub: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub(crate) aud: Option<String>,
 	pub(crate) exp: u64,
-}
\ No newline at end of file
+	/// not before, token is rejected before this time
+	#[serde(default)]
+	pub(crate) nbf: u64,
+	/// issued at
+	pub(crate) iat: u64,
+	/// issued at in milliseconds, use to check "log out everywhere"
+	/// (`iat` can't tell if token is issued right after it in the same second)
+	#[serde(default)]
+	pub(crate) iat_ms: u64,
+	/// unique token id, use to revoke single token
+	pub(crate) jti: String,
+	/// roles of user at the time token is created
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub(crate) roles: Vec<String>,
+	/// space separated permissions like `users:read orders:write`
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub(crate) scope: String,
+}
+
+impl Jwt {
+	/// issued at in milliseconds, fallback to `iat` for token without `iat_ms`
+	pub fn issued_at_ms(&self) -> u64 {
+		if self.iat_ms > 0 { self.iat_ms } else { self.iat * 1000 }
+	}
+
+	/// check if token has role
+	pub fn has_role(&self, role: &str) -> bool {
+		self.roles.iter().any(|it| it == role)
+	}
+
+	/// check if token has permission (wildcard in token is supported)
+	pub fn has_permission(&self, permission: &str) -> bool {
+		self.scope.split_whitespace().any(|granted| permission_matches(granted, permission))
+	}
+}
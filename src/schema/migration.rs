@@ -0,0 +1,38 @@
+use mongodb::bson::DateTime;
+use serde::{Serialize, Deserialize};
+
+/// this struct record migration applied to database
+#[derive(Serialize, Deserialize)]
+pub struct MigrationRecord {
+	/// version of migration
+	pub(crate) _id: i64,
+	/// name of migration
+	pub(crate) name: String,
+	pub(crate) applied_at: DateTime,
+}
+
+impl MigrationRecord {
+	/// record migration as applied now
+	pub fn new(version: i64, name: String) -> Self {
+		Self {
+			_id: version,
+			name,
+			applied_at: DateTime::now(),
+		}
+	}
+
+	/// get version of migration
+	pub fn version(&self) -> i64 {
+		self._id
+	}
+
+	/// get name of migration
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// get time when migration was applied
+	pub fn applied_at(&self) -> DateTime {
+		self.applied_at
+	}
+}
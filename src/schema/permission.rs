@@ -0,0 +1,85 @@
+/// check if `granted` permission cover `required` permission
+/// + `*` grant everything
+/// + `users:*` grant every action of `users` like `users:read`
+/// + otherwise permission must be equal
+///
+/// empty permission never match
+pub fn permission_matches(granted: &str, required: &str) -> bool {
+	if granted.is_empty() || required.is_empty() {
+		return false;
+	}
+	if granted == "*" || granted == required {
+		return true;
+	}
+	match (granted.strip_suffix(":*"), required.split_once(':')) {
+		(Some(resource), Some((required_resource, _))) => !resource.is_empty() && resource == required_resource,
+		_ => false
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::permission_matches;
+	use crate::schema::Jwt;
+
+	fn jwt_with_scope(scope: &str) -> Jwt {
+		Jwt {
+			iss: None,
+			sub: "user".to_string(),
+			aud: None,
+			exp: 0,
+			nbf: 0,
+			iat: 0,
+			iat_ms: 0,
+			jti: String::new(),
+			roles: Vec::new(),
+			scope: scope.to_string(),
+		}
+	}
+
+	#[test]
+	fn exact() {
+		assert!(permission_matches("users:read", "users:read"));
+		assert!(!permission_matches("users:read", "users:write"));
+		assert!(!permission_matches("users:read", "orders:read"));
+	}
+
+	#[test]
+	fn wildcard() {
+		assert!(permission_matches("*", "users:read"));
+		assert!(permission_matches("*", "anything"));
+		assert!(permission_matches("users:*", "users:read"));
+		assert!(permission_matches("users:*", "users:read:self"));
+		assert!(!permission_matches("users:*", "orders:read"));
+		// wildcard only grant action of resource, not the resource name alone
+		assert!(!permission_matches("users:*", "users"));
+		// `*` in required permission isn't a wildcard
+		assert!(!permission_matches("users:read", "users:*"));
+		assert!(!permission_matches(":*", ":read"));
+	}
+
+	#[test]
+	fn prefix_confusion() {
+		assert!(!permission_matches("user:read", "user:readall"));
+		assert!(!permission_matches("user:readall", "user:read"));
+		assert!(!permission_matches("user:*", "users:read"));
+		assert!(!permission_matches("users:*", "user:read"));
+		assert!(!permission_matches("users", "users:read"));
+	}
+
+	#[test]
+	fn empty() {
+		assert!(!permission_matches("", ""));
+		assert!(!permission_matches("", "users:read"));
+		assert!(!permission_matches("users:read", ""));
+		assert!(!permission_matches("*", ""));
+	}
+
+	#[test]
+	fn scope_of_token() {
+		assert!(!jwt_with_scope("").has_permission("users:read"));
+		assert!(!jwt_with_scope("   ").has_permission("users:read"));
+		assert!(jwt_with_scope("orders:read users:*").has_permission("users:write"));
+		assert!(!jwt_with_scope("user:readall").has_permission("user:read"));
+	}
+}
@@ -0,0 +1,39 @@
+use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use serde::{Serialize, Deserialize};
+
+/// this struct store refresh token (hashed) issued to user
+///
+/// every refresh token belong to a family which start at login,
+/// rotating a token create new token in the same family
+#[derive(Serialize, Deserialize)]
+pub struct RefreshToken {
+	pub(crate) _id: ObjectId,
+	/// owner of this token
+	pub(crate) user_id: ObjectId,
+	/// id of token chain started from login
+	pub(crate) family: ObjectId,
+	/// sha256 of token (hex) so leaked database can't be used to refresh
+	pub(crate) token_hash: String,
+	/// set to true once token has been exchanged
+	pub(crate) used: bool,
+	/// set to true when whole family is revoked
+	pub(crate) revoked: bool,
+	/// mongodb will remove document after this time (TTL index)
+	pub(crate) expire_at: DateTime,
+}
+
+impl RefreshToken {
+	/// create new refresh token record
+	pub fn new(user_id: ObjectId, family: ObjectId, token_hash: String, expire_at: DateTime) -> Self {
+		Self {
+			_id: ObjectId::new(),
+			user_id,
+			family,
+			token_hash,
+			used: false,
+			revoked: false,
+			expire_at,
+		}
+	}
+}
@@ -0,0 +1,41 @@
+use mongodb::bson::DateTime;
+use serde::{Serialize, Deserialize};
+
+/// this struct store revoked jwt, document will be removed once token is expired anyway
+///
+/// `_id` is either
+/// + `jti` of revoked token
+/// + `user:<user id>` with `revoked_before` (milliseconds) to revoke every token of user issued before that time
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevokedToken {
+	pub(crate) _id: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub(crate) revoked_before: Option<u64>,
+	/// mongodb will remove document after this time (TTL index)
+	pub(crate) expire_at: DateTime,
+}
+
+impl RevokedToken {
+	/// revoke single token by `jti`
+	pub fn token(jti: String, expire_at: DateTime) -> Self {
+		Self {
+			_id: jti,
+			revoked_before: None,
+			expire_at,
+		}
+	}
+
+	/// revoke every token of user issued before `revoked_before`
+	pub fn user(user_id: &str, revoked_before: u64, expire_at: DateTime) -> Self {
+		Self {
+			_id: Self::user_key(user_id),
+			revoked_before: Some(revoked_before),
+			expire_at,
+		}
+	}
+
+	/// get `_id` of user-wide revocation
+	pub fn user_key(user_id: &str) -> String {
+		format!("user:{}", user_id)
+	}
+}
@@ -0,0 +1,31 @@
+use serde::{Serialize, Deserialize};
+
+/// this struct store permissions granted to every user having the role
+#[derive(Serialize, Deserialize)]
+pub struct RolePermissions {
+	/// name of role
+	pub(crate) _id: String,
+	/// permission like `users:read`, `users:*` or `*`
+	#[serde(default)]
+	pub(crate) permissions: Vec<String>,
+}
+
+impl RolePermissions {
+	/// create role with permissions
+	pub fn new(name: String, permissions: Vec<String>) -> Self {
+		Self {
+			_id: name,
+			permissions,
+		}
+	}
+
+	/// get name of role
+	pub fn name(&self) -> &str {
+		&self._id
+	}
+
+	/// get permissions granted by role
+	pub fn permissions(&self) -> &[String] {
+		&self.permissions
+	}
+}
@@ -1,7 +1,11 @@
+use std::time::Instant;
+
 use bcrypt::{hash, verify};
 use mongodb::bson::oid::ObjectId;
 use serde::{Serialize, Deserialize};
 
+use crate::metrics::{observe_since, PASSWORD_VERIFY_DURATION};
+
 /// this struct store user information
 #[derive(Serialize, Deserialize)]
 pub struct User {
@@ -9,6 +13,12 @@ pub struct User {
 	username: String,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	password: Option<String>,
+	/// roles of user, embedded into jwt when token is created
+	#[serde(default)]
+	roles: Vec<String>,
+	/// permissions granted to user directly (in addition to permissions of roles)
+	#[serde(default)]
+	permissions: Vec<String>,
 }
 
 impl User {
@@ -18,6 +28,8 @@ impl User {
 			_id: Default::default(),
 			username,
 			password: None,
+			roles: Vec::new(),
+			permissions: Vec::new(),
 		}
 	}
 
@@ -26,6 +38,60 @@ impl User {
 		&self._id
 	}
 
+	/// get username
+	pub fn username(&self) -> &str {
+		&self.username
+	}
+
+	/// get roles of user
+	pub fn roles(&self) -> &[String] {
+		&self.roles
+	}
+
+	/// check if user has role
+	pub fn has_role(&self, role: &str) -> bool {
+		self.roles.iter().any(|it| it == role)
+	}
+
+	/// add role to user, return false if user already has it
+	pub fn add_role(&mut self, role: impl Into<String>) -> bool {
+		let role = role.into();
+		if self.has_role(&role) {
+			return false;
+		}
+		self.roles.push(role);
+		true
+	}
+
+	/// get permissions granted to user directly
+	pub fn permissions(&self) -> &[String] {
+		&self.permissions
+	}
+
+	/// grant permission to user, return false if user already has it
+	pub fn grant_permission(&mut self, permission: impl Into<String>) -> bool {
+		let permission = permission.into();
+		if self.permissions.contains(&permission) {
+			return false;
+		}
+		self.permissions.push(permission);
+		true
+	}
+
+	/// revoke permission from user, return false if user doesn't have it
+	pub fn revoke_permission(&mut self, permission: &str) -> bool {
+		let len = self.permissions.len();
+		self.permissions.retain(|it| it != permission);
+		self.permissions.len() != len
+	}
+
+	/// remove role from user, return false if user doesn't have it
+	pub fn remove_role(&mut self, role: &str) -> bool {
+		let len = self.roles.len();
+		self.roles.retain(|it| it != role);
+		self.roles.len() != len
+	}
+
 	/// check if user's password is correct
 	pub async fn verify_password(&self, password: impl AsRef<[u8]>) -> bool {
 		if let Some(hash) = &self.password {
@@ -33,7 +99,10 @@ impl User {
 			let hash = hash.to_string();
 			// prevent verify from blocking executor
 			tokio_rayon::spawn_fifo(move || {
-				verify(password, hash.as_str()).unwrap_or_default()// default: false
+				let start = Instant::now();
+				let valid = verify(password, hash.as_str()).unwrap_or_default();// default: false
+				observe_since(&PASSWORD_VERIFY_DURATION, start);
+				valid
 			}).await
 		} else {
 			false
@@ -5,4 +5,11 @@ pub mod time;
 pub mod env;
 
 /// extension for boolean parsing
-pub mod bool_ext;
\ No newline at end of file
+pub mod bool_ext;
+
+/// this module set up log output
+pub mod logging;
+
+/// this module export trace to OpenTelemetry collector
+#[cfg(feature = "otel")]
+pub mod otel;
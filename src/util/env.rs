@@ -16,4 +16,31 @@ pub fn raw_env(key: &str) -> Option<Vec<u8>> {
 	{
 		Some(std::env::var_os(key)?.to_string_lossy().as_bytes().to_vec())
 	}
-}
\ No newline at end of file
+}
+/// read secret from file, used for `*_FILE` env var (docker / kubernetes secret)
+/// + surrounding whitespace like trailing new line is trimmed
+/// + file must be regular file, not writable by group or other and not empty
+pub fn read_secret_file(path: impl AsRef<std::path::Path>) -> anyhow::Result<Vec<u8>> {
+	use anyhow::{bail, Context};
+
+	let path = path.as_ref();
+	let metadata = std::fs::metadata(path).with_context(|| format!("can't access {}", path.display()))?;
+	if !metadata.is_file() {
+		bail!("{} is not a file", path.display());
+	}
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		let mode = metadata.permissions().mode();
+		if mode & 0o022 != 0 {
+			bail!("{} is writable by group or other (mode {:o}), run `chmod go-w` on it", path.display(), mode & 0o777);
+		}
+	}
+
+	let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+	let data = data.trim_ascii();
+	if data.is_empty() {
+		bail!("{} is empty", path.display());
+	}
+	Ok(data.to_vec())
+}
@@ -0,0 +1,20 @@
+use tracing_subscriber::{EnvFilter, fmt};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use super::env::env;
+
+/// init tracing subscriber for binary
+/// + level is set by `RUST_LOG` (default: `info`)
+/// + `LOG_FORMAT=json` print one json object per line with current span, otherwise human readable text
+/// + with `otel` feature, spans are also exported over OTLP (see [super::otel])
+pub fn init_logging() {
+	let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+	let registry = tracing_subscriber::registry().with(filter);
+	#[cfg(feature = "otel")]
+	let registry = registry.with(super::otel::layer());
+	match env("LOG_FORMAT").as_deref() {
+		Some("json") => registry.with(fmt::layer().json().with_current_span(true).with_span_list(false)).init(),
+		_ => registry.with(fmt::layer()).init(),
+	}
+}
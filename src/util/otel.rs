@@ -0,0 +1,125 @@
+use std::sync::OnceLock;
+
+use actix_web::http::header::HeaderMap;
+use opentelemetry::Context;
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Subscriber;
+use tracing_subscriber::Layer;
+use tracing_subscriber::registry::LookupSpan;
+
+use super::bool_ext::BoolExt;
+use super::env::env;
+
+/// provider kept to flush remaining spans on shutdown
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// create layer export spans over OTLP (http/protobuf)
+///
+/// exporter is configured by standard OpenTelemetry env
+/// + `OTEL_EXPORTER_OTLP_ENDPOINT` / `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` (default: `http://localhost:4318`)
+/// + `OTEL_SERVICE_NAME` (default: crate name), `OTEL_RESOURCE_ATTRIBUTES`
+/// + `OTEL_SDK_DISABLED=true` disable export
+pub(super) fn layer<S>() -> Option<impl Layer<S>>
+	where S: Subscriber + for<'span> LookupSpan<'span> {
+	if env("OTEL_SDK_DISABLED").may_true() {
+		return None;
+	}
+	// logging isn't ready yet, print to stderr
+	let provider = match tracer_provider() {
+		Ok(provider) => provider,
+		Err(err) => {
+			eprintln!("failed to create OTLP exporter, trace is not exported: {}", err);
+			return None;
+		}
+	};
+	let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+
+	global::set_text_map_propagator(TraceContextPropagator::new());
+	global::set_tracer_provider(provider.clone());
+	let _ = PROVIDER.set(provider);
+	Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// build provider batching spans to OTLP exporter, doesn't connect until first export
+fn tracer_provider() -> Result<SdkTracerProvider, ExporterBuildError> {
+	let exporter = SpanExporter::builder().with_http().build()?;
+	let mut resource = Resource::builder();
+	if env("OTEL_SERVICE_NAME").is_none() {
+		resource = resource.with_service_name(env!("CARGO_PKG_NAME"));
+	}
+	Ok(SdkTracerProvider::builder()
+		.with_batch_exporter(exporter)
+		.with_resource(resource.build())
+		.build())
+}
+
+/// export spans which are still buffered, should be called before process exit
+pub fn shutdown() {
+	if let Some(provider) = PROVIDER.get() {
+		if let Err(err) = provider.shutdown() {
+			log::warn!("failed to flush OTLP spans: {}", err);
+		}
+	}
+}
+
+/// read W3C `traceparent` / `tracestate` of incoming request
+pub fn extract_context(headers: &HeaderMap) -> Context {
+	global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// [Extractor] for actix header map
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+	fn get(&self, key: &str) -> Option<&str> {
+		self.0.get(key).and_then(|it| it.to_str().ok())
+	}
+
+	fn keys(&self) -> Vec<&str> {
+		self.0.keys().map(|it| it.as_str()).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use opentelemetry::trace::{TraceContextExt, TracerProvider};
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+	use tracing_subscriber::layer::SubscriberExt;
+
+	use super::*;
+
+	#[test]
+	fn pipeline_without_collector() {
+		// nothing listen on default endpoint, building must not need collector
+		let provider = tracer_provider().unwrap();
+		let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("test"));
+		let subscriber = tracing_subscriber::registry().with(layer);
+		tracing::subscriber::with_default(subscriber, || {
+			let span = tracing::info_span!("request");
+			let context = span.context();
+			assert!(context.span().span_context().is_valid());
+			assert!(context.span().span_context().is_sampled());
+		});
+	}
+
+	#[test]
+	fn extract_traceparent() {
+		global::set_text_map_propagator(TraceContextPropagator::new());
+		let mut headers = HeaderMap::new();
+		headers.insert(
+			"traceparent".parse().unwrap(),
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().unwrap(),
+		);
+		let context = extract_context(&headers);
+		let span_context = context.span().span_context().clone();
+		assert!(span_context.is_remote());
+		assert_eq!(span_context.trace_id().to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+		assert_eq!(span_context.span_id().to_string(), "00f067aa0ba902b7");
+	}
+}
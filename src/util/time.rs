@@ -13,6 +13,12 @@ pub fn timestamp_u128() -> u128 {
 	SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()
 }
 
+/// get current timestamp in seconds (unix time used by jwt)
+#[inline]
+pub fn timestamp_sec() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
 /// timestamp helper use to get relative timestamp from now
 pub trait TimestampExt<T> {
 	/// get relative timestamp from now result should return as `current_timestamp + self.milliseconds()`
@@ -1,12 +1,26 @@
+use std::fmt::{Display, Formatter};
+
+use actix_web::{Error, HttpResponse, ResponseError};
 use actix_web::http::StatusCode;
+use jsonwebtoken::errors::ErrorKind as JwtErrorKind;
 use serde::{Serialize, Deserialize};
 
+use crate::auth::{LoginError, RegisterError};
+use crate::auth::refresh::RefreshError;
+use crate::repository::{is_duplicate_key, RepositoryError};
+
 /// api status this should attach to any api response
 #[derive(Serialize, Deserialize)]
 pub struct ApiStatus {
 	ok: bool,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	error: Option<String>,
+	/// machine-readable error code, see [ApiError::code]
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	code: Option<String>,
+	/// what part of request is invalid, see [ApiError::InvalidInput]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	details: Vec<FieldError>,
 }
 
 impl ApiStatus {
@@ -15,6 +29,8 @@ impl ApiStatus {
 		Self {
 			ok: true,
 			error: None,
+			code: None,
+			details: Vec::new(),
 		}
 	}
 
@@ -23,10 +39,52 @@ impl ApiStatus {
 		Self {
 			ok: false,
 			error: Some(message),
+			code: None,
+			details: Vec::new(),
+		}
+	}
+
+	/// same as [ApiStatus::error] with machine-readable code
+	pub fn error_with_code(message: String, code: &str) -> Self {
+		Self {
+			ok: false,
+			error: Some(message),
+			code: Some(code.to_string()),
+			details: Vec::new(),
 		}
 	}
 }
 
+/// part of request a value is read from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Location {
+	/// json / form body
+	Body,
+	/// query string
+	Query,
+	/// path parameter
+	Path,
+}
+
+/// value of request that failed to deserialize
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FieldError {
+	/// where the value is read from
+	pub location: Location,
+	/// name of field, only known when it's missing / unknown / duplicated
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub field: Option<String>,
+	/// position in json body
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub line: Option<usize>,
+	/// column of [FieldError::line]
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub column: Option<usize>,
+	/// reason from deserializer like ``invalid type: integer `1`, expected a string``
+	pub message: String,
+}
+
 impl From<StatusCode> for ApiStatus {
 	fn from(code: StatusCode) -> Self {
 		if code.is_success() {
@@ -35,4 +93,232 @@ impl From<StatusCode> for ApiStatus {
 			ApiStatus::error(format!("{:?}", code))
 		}
 	}
-}
\ No newline at end of file
+}
+
+impl From<&ApiError> for ApiStatus {
+	fn from(err: &ApiError) -> Self {
+		let mut status = ApiStatus::error_with_code(err.to_string(), err.code());
+		if let ApiError::InvalidInput(detail) = err {
+			status.details.push(detail.clone());
+		}
+		status
+	}
+}
+
+/// error returned from handler / extractor, always response as [ApiStatus] json
+/// ```rust
+/// use actix_web::{get, HttpResponse};
+/// use actix_mongo_jwt_web_template::web::error::ApiError;
+/// #[get("/")]
+/// async fn index() -> Result<HttpResponse, ApiError> {
+///     Err(ApiError::NotFound)
+/// }
+/// ```
+#[derive(Debug)]
+pub enum ApiError {
+	/// 400 request is invalid, message is shown to client
+	BadRequest(String),
+	/// 400 body / query / path can't be deserialized, detail is shown to client
+	InvalidInput(FieldError),
+	/// 401 username or password is wrong
+	InvalidCredentials,
+	/// 401 `Authorization` header is missing
+	MissingToken,
+	/// 401 token is malformed or signature doesn't match
+	InvalidToken,
+	/// 401 token `exp` has passed
+	ExpiredToken,
+	/// 401 token `nbf` is in the future
+	ImmatureToken,
+	/// 401 token has been revoked (logout)
+	RevokedToken,
+	/// 401 refresh token is unknown or expired
+	InvalidRefreshToken,
+	/// 401 refresh token is used again, every token of the same login is revoked
+	RevokedRefreshToken,
+	/// 401 client doesn't present tls certificate
+	MissingClientCertificate,
+	/// 401 client certificate can't be parsed or doesn't belong to any user
+	InvalidClientCertificate,
+	/// 403 token doesn't have required role / permission
+	Forbidden,
+	/// 404 route or resource doesn't exist
+	NotFound,
+	/// 409 resource already exists, message is shown to client
+	Conflict(String),
+	/// 413 body is larger than limit (bytes)
+	PayloadTooLarge(usize),
+	/// 415 body isn't json / form as expected by handler
+	UnsupportedMediaType,
+	/// 503 index / migration isn't done yet, see [crate::web::preload::RequirePreloaded]
+	Unavailable,
+	/// 500 detail is logged instead of shown to client
+	Internal(anyhow::Error),
+}
+
+impl ApiError {
+	/// stable machine-readable code, client should use this instead of message
+	pub fn code(&self) -> &'static str {
+		match self {
+			ApiError::BadRequest(_) => "bad_request",
+			ApiError::InvalidInput(detail) => match detail.location {
+				Location::Body => "invalid_body",
+				Location::Query => "invalid_query",
+				Location::Path => "invalid_path",
+			},
+			ApiError::InvalidCredentials => "invalid_credentials",
+			ApiError::MissingToken => "missing_token",
+			ApiError::InvalidToken => "invalid_token",
+			ApiError::ExpiredToken => "expired_token",
+			ApiError::ImmatureToken => "immature_token",
+			ApiError::RevokedToken => "revoked_token",
+			ApiError::InvalidRefreshToken => "invalid_refresh_token",
+			ApiError::RevokedRefreshToken => "revoked_refresh_token",
+			ApiError::MissingClientCertificate => "missing_client_certificate",
+			ApiError::InvalidClientCertificate => "invalid_client_certificate",
+			ApiError::Forbidden => "forbidden",
+			ApiError::NotFound => "not_found",
+			ApiError::Conflict(_) => "conflict",
+			ApiError::PayloadTooLarge(_) => "payload_too_large",
+			ApiError::UnsupportedMediaType => "unsupported_media_type",
+			ApiError::Unavailable => "unavailable",
+			ApiError::Internal(_) => "internal_error",
+		}
+	}
+}
+
+impl Display for ApiError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ApiError::BadRequest(message) | ApiError::Conflict(message) => f.write_str(message),
+			ApiError::InvalidInput(detail) => match detail.location {
+				Location::Body => f.write_str("Invalid request body!"),
+				Location::Query => f.write_str("Invalid query string!"),
+				Location::Path => f.write_str("Invalid path parameter!"),
+			},
+			ApiError::InvalidCredentials => f.write_str("Invalid username or password!"),
+			ApiError::MissingToken => f.write_str("Missing token!"),
+			ApiError::InvalidToken => f.write_str("Invalid token!"),
+			ApiError::ExpiredToken => f.write_str("Expired token!"),
+			ApiError::ImmatureToken => f.write_str("Token is not valid yet!"),
+			ApiError::RevokedToken => f.write_str("Revoked token!"),
+			ApiError::InvalidRefreshToken => f.write_str("Invalid refresh token"),
+			ApiError::RevokedRefreshToken => f.write_str("Refresh token has been revoked"),
+			ApiError::MissingClientCertificate => f.write_str("Missing client certificate!"),
+			ApiError::InvalidClientCertificate => f.write_str("Invalid client certificate!"),
+			ApiError::Forbidden => f.write_str("Forbidden"),
+			ApiError::NotFound => f.write_str("Not Found"),
+			ApiError::PayloadTooLarge(limit) => write!(f, "Request body is larger than {} bytes", limit),
+			ApiError::UnsupportedMediaType => f.write_str("Unsupported content type"),
+			ApiError::Unavailable => f.write_str("Server is starting, try again later"),
+			ApiError::Internal(_) => f.write_str("Internal Server Error"),
+		}
+	}
+}
+
+impl ResponseError for ApiError {
+	fn status_code(&self) -> StatusCode {
+		match self {
+			ApiError::BadRequest(_) | ApiError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+			ApiError::InvalidCredentials
+			| ApiError::MissingToken
+			| ApiError::InvalidToken
+			| ApiError::ExpiredToken
+			| ApiError::ImmatureToken
+			| ApiError::RevokedToken
+			| ApiError::InvalidRefreshToken
+			| ApiError::RevokedRefreshToken
+			| ApiError::MissingClientCertificate
+			| ApiError::InvalidClientCertificate => StatusCode::UNAUTHORIZED,
+			ApiError::Forbidden => StatusCode::FORBIDDEN,
+			ApiError::NotFound => StatusCode::NOT_FOUND,
+			ApiError::Conflict(_) => StatusCode::CONFLICT,
+			ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+			ApiError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			ApiError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+			ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+		}
+	}
+
+	fn error_response(&self) -> HttpResponse {
+		if let ApiError::Internal(err) = self {
+			log::error!("internal error: {:?}", err);
+		}
+		HttpResponse::build(self.status_code()).json(ApiStatus::from(self))
+	}
+}
+
+impl From<anyhow::Error> for ApiError {
+	fn from(err: anyhow::Error) -> Self {
+		ApiError::Internal(err)
+	}
+}
+
+impl From<mongodb::error::Error> for ApiError {
+	fn from(err: mongodb::error::Error) -> Self {
+		if is_duplicate_key(&err) {
+			ApiError::Conflict("Already exists".to_string())
+		} else {
+			ApiError::Internal(err.into())
+		}
+	}
+}
+
+impl From<RepositoryError> for ApiError {
+	fn from(err: RepositoryError) -> Self {
+		match err {
+			RepositoryError::DuplicateKey(_) => ApiError::Conflict("Already exists".to_string()),
+			RepositoryError::Database(err) => ApiError::Internal(err.into()),
+		}
+	}
+}
+
+impl From<jsonwebtoken::errors::Error> for ApiError {
+	fn from(err: jsonwebtoken::errors::Error) -> Self {
+		match err.kind() {
+			JwtErrorKind::ExpiredSignature => ApiError::ExpiredToken,
+			JwtErrorKind::ImmatureSignature => ApiError::ImmatureToken,
+			// problem of our key, not token from client
+			JwtErrorKind::InvalidEcdsaKey
+			| JwtErrorKind::InvalidRsaKey(_)
+			| JwtErrorKind::RsaFailedSigning
+			| JwtErrorKind::InvalidKeyFormat
+			| JwtErrorKind::Crypto(_) => ApiError::Internal(err.into()),
+			_ => ApiError::InvalidToken,
+		}
+	}
+}
+
+impl From<LoginError> for ApiError {
+	fn from(err: LoginError) -> Self {
+		match err {
+			LoginError::InvalidCredentials => ApiError::InvalidCredentials,
+			LoginError::Internal(err) => ApiError::Internal(err),
+		}
+	}
+}
+
+impl From<RegisterError> for ApiError {
+	fn from(err: RegisterError) -> Self {
+		match err {
+			RegisterError::Invalid(reason) => ApiError::BadRequest(reason),
+			RegisterError::UsernameTaken => ApiError::Conflict("Username is already taken".to_string()),
+			RegisterError::Internal(err) => ApiError::Internal(err),
+		}
+	}
+}
+
+impl From<RefreshError> for ApiError {
+	fn from(err: RefreshError) -> Self {
+		match err {
+			RefreshError::Invalid => ApiError::InvalidRefreshToken,
+			RefreshError::Reused => ApiError::RevokedRefreshToken,
+			RefreshError::Internal(err) => ApiError::Internal(err),
+		}
+	}
+}
+
+/// error with 403 status and `ApiStatus` json body, use when token doesn't have required role / permission
+pub fn forbidden() -> Error {
+	ApiError::Forbidden.into()
+}
@@ -0,0 +1,102 @@
+use actix_web::error::{JsonPayloadError, PathError, PayloadError, QueryPayloadError, UrlencodedError};
+use actix_web::web::{FormConfig, JsonConfig, PathConfig, PayloadConfig, QueryConfig};
+
+use crate::web::error::{ApiError, FieldError, Location};
+
+/// default limit of actix `FormConfig`, kept so form body isn't raised to json limit
+const FORM_LIMIT: usize = 16 * 1024;
+
+/// default limit of actix `PayloadConfig` (`Bytes` / `String` body), reported by [crate::web::problem::ProblemResponse]
+pub(crate) const PAYLOAD_LIMIT: usize = 256 * 1024;
+
+/// `Json` extractor config, malformed body / wrong content type / body over `limit` bytes
+/// respond [ApiError] instead of plain text
+/// ```rust
+/// use actix_web::App;
+/// use actix_mongo_jwt_web_template::web::extract;
+/// App::new().app_data(extract::json_config(2 * 1024 * 1024));
+/// ```
+pub fn json_config(limit: usize) -> JsonConfig {
+	JsonConfig::default().limit(limit).error_handler(move |err, _| {
+		match err {
+			JsonPayloadError::Deserialize(err) => ApiError::InvalidInput(json_field_error(&err)),
+			JsonPayloadError::OverflowKnownLength { limit, .. } | JsonPayloadError::Overflow { limit } => ApiError::PayloadTooLarge(limit),
+			JsonPayloadError::ContentType => ApiError::UnsupportedMediaType,
+			JsonPayloadError::Payload(err) => payload_error(err, limit),
+			JsonPayloadError::Serialize(err) => ApiError::Internal(err.into()),
+			err => ApiError::BadRequest(err.to_string()),
+		}.into()
+	})
+}
+
+/// `Form` extractor config, same as [json_config] for `application/x-www-form-urlencoded` body with 16 KiB limit
+pub fn form_config() -> FormConfig {
+	FormConfig::default().limit(FORM_LIMIT).error_handler(|err, _| {
+		match err {
+			UrlencodedError::Parse(err) => ApiError::InvalidInput(field_error(Location::Body, err.to_string())),
+			UrlencodedError::Overflow { limit, .. } => ApiError::PayloadTooLarge(limit),
+			UrlencodedError::ContentType => ApiError::UnsupportedMediaType,
+			UrlencodedError::Payload(err) => payload_error(err, FORM_LIMIT),
+			UrlencodedError::Serialize(err) => ApiError::Internal(err.into()),
+			err => ApiError::BadRequest(err.to_string()),
+		}.into()
+	})
+}
+
+/// `Bytes` / `String` extractor config with actix default 256 KiB limit,
+/// it can't have error handler so body over limit is turned into [ApiError::PayloadTooLarge] by [crate::web::problem::ProblemResponse]
+pub fn payload_config() -> PayloadConfig {
+	PayloadConfig::new(PAYLOAD_LIMIT)
+}
+
+/// `Query` extractor config, invalid query string respond [ApiError::InvalidInput]
+pub fn query_config() -> QueryConfig {
+	QueryConfig::default().error_handler(|err, _| {
+		match err {
+			QueryPayloadError::Deserialize(err) => ApiError::InvalidInput(field_error(Location::Query, err.to_string())),
+			err => ApiError::BadRequest(err.to_string()),
+		}.into()
+	})
+}
+
+/// `Path` extractor config, path parameter of wrong type respond [ApiError::InvalidInput]
+pub fn path_config() -> PathConfig {
+	PathConfig::default().error_handler(|err, _| {
+		match err {
+			PathError::Deserialize(err) => ApiError::InvalidInput(field_error(Location::Path, err.to_string())),
+			err => ApiError::BadRequest(err.to_string()),
+		}.into()
+	})
+}
+
+/// overflow while reading body without `Content-Length`
+fn payload_error(err: PayloadError, limit: usize) -> ApiError {
+	match err {
+		PayloadError::Overflow => ApiError::PayloadTooLarge(limit),
+		err => ApiError::BadRequest(err.to_string()),
+	}
+}
+
+fn json_field_error(err: &serde_json::Error) -> FieldError {
+	let message = err.to_string();
+	// position is reported in its own fields
+	let suffix = format!(" at line {} column {}", err.line(), err.column());
+	let mut detail = field_error(Location::Body, message.strip_suffix(&suffix).unwrap_or(&message).to_string());
+	if err.line() > 0 {
+		detail.line = Some(err.line());
+		detail.column = Some(err.column());
+	}
+	detail
+}
+
+fn field_error(location: Location, message: String) -> FieldError {
+	FieldError { location, field: field_name(&message), line: None, column: None, message }
+}
+
+/// serde only name the field in error of missing / unknown / duplicate field, e.g. ``missing field `password` ``
+fn field_name(message: &str) -> Option<String> {
+	["missing field `", "unknown field `", "duplicate field `"].iter().find_map(|prefix| {
+		let rest = &message[message.find(prefix)? + prefix.len()..];
+		rest.find('`').map(|end| rest[..end].to_string())
+	})
+}
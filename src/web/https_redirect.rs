@@ -0,0 +1,74 @@
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::{Error, HttpResponse};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::LOCATION;
+
+/// middleware redirect request received over plain http to https with `308 Permanent Redirect`
+/// (method and body are kept), request over https is passed through.
+/// Wrap only scope served to client, probe of `/health/*` following redirect would pass without reaching the app
+/// ```rust
+/// use actix_web::web;
+/// use actix_mongo_jwt_web_template::web::https_redirect::HttpsRedirect;
+/// web::scope("").wrap(HttpsRedirect::new("example.com:8443"));
+/// ```
+#[derive(Clone)]
+pub struct HttpsRedirect {
+	origin: Rc<str>,
+}
+
+impl HttpsRedirect {
+	/// redirect to `https://{host}`, `host` is configured instead of taken from `Host` header
+	/// so client can't make it redirect to other site
+	pub fn new(host: &str) -> Self {
+		Self { origin: Rc::from(format!("https://{}", host)) }
+	}
+}
+
+impl<S, B> Transform<S, ServiceRequest> for HttpsRedirect
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Transform = HttpsRedirectMiddleware<S>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(HttpsRedirectMiddleware { service, origin: self.origin.clone() }))
+	}
+}
+
+/// service created by [HttpsRedirect]
+pub struct HttpsRedirectMiddleware<S> {
+	service: S,
+	origin: Rc<str>,
+}
+
+impl<S, B> Service<ServiceRequest> for HttpsRedirectMiddleware<S>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		// set by actix for connection accepted by tls listener
+		if req.app_config().secure() {
+			let res = self.service.call(req);
+			return Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) });
+		}
+
+		let path = req.uri().path_and_query().map(|it| it.as_str()).unwrap_or("/");
+		let location = format!("{}{}", self.origin, path);
+		let res = HttpResponse::PermanentRedirect().insert_header((LOCATION, location)).finish();
+		Box::pin(ready(Ok(req.into_response(res).map_into_right_body())))
+	}
+}
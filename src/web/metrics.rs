@@ -0,0 +1,86 @@
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::Instant;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use actix_web::http::Method;
+
+use crate::metrics::{HTTP_REQUEST_DURATION, HTTP_REQUESTS};
+
+/// route label of request which doesn't match any route (prevent unbounded label value)
+const UNMATCHED: &str = "<unmatched>";
+
+/// middleware record request count and latency by route pattern (e.g. `/auth/login`)
+/// ```rust
+/// use actix_web::App;
+/// use actix_mongo_jwt_web_template::web::metrics::RequestMetrics;
+/// App::new().wrap(RequestMetrics);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Transform = RequestMetricsMiddleware<S>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(RequestMetricsMiddleware { service: Rc::new(service) }))
+	}
+}
+
+/// service created by [RequestMetrics]
+pub struct RequestMetricsMiddleware<S> {
+	service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		let service = self.service.clone();
+		let start = Instant::now();
+		let method = method_label(req.method());
+		// resolved from resource map so it's known before routing, and when inner service return error
+		// (e.g. 401 / 403 from `RequireRoles`) the request isn't given back
+		let route = req.match_pattern().unwrap_or_else(|| UNMATCHED.to_string());
+		Box::pin(async move {
+			let result = service.call(req).await;
+			let status = match &result {
+				Ok(res) => res.status(),
+				Err(err) => err.as_response_error().status_code(),
+			};
+			HTTP_REQUESTS.with_label_values(&[method, &route, status.as_str()]).inc();
+			HTTP_REQUEST_DURATION.with_label_values(&[method, &route]).observe(start.elapsed().as_secs_f64());
+			result
+		})
+	}
+}
+
+/// method label of request, client can send any method token so non-standard one is `other`
+fn method_label(method: &Method) -> &'static str {
+	match *method {
+		Method::GET => "GET",
+		Method::POST => "POST",
+		Method::PUT => "PUT",
+		Method::DELETE => "DELETE",
+		Method::HEAD => "HEAD",
+		Method::OPTIONS => "OPTIONS",
+		Method::CONNECT => "CONNECT",
+		Method::PATCH => "PATCH",
+		Method::TRACE => "TRACE",
+		_ => "other",
+	}
+}
@@ -1,2 +1,20 @@
 /// global error / helper
-pub mod error;
\ No newline at end of file
+pub mod error;
+
+/// extractor config respond `ApiStatus` json when request can't be deserialized
+pub mod extract;
+
+/// middleware record request metrics
+pub mod metrics;
+
+/// RFC 7807 problem details error format
+pub mod problem;
+
+/// middleware redirect plain http request to https
+pub mod https_redirect;
+
+/// middleware reject request until database is preloaded
+pub mod preload;
+
+/// middleware assign request id and open tracing span per request
+pub mod request_id;
\ No newline at end of file
@@ -0,0 +1,58 @@
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+
+use actix_web::Error;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+
+use crate::manager::database::is_preloaded;
+use crate::web::error::ApiError;
+
+/// middleware respond 503 ([ApiError::Unavailable]) until index is created and migration is applied,
+/// so request can't write data the unique index would have rejected
+/// ```rust
+/// use actix_web::web;
+/// use actix_mongo_jwt_web_template::web::preload::RequirePreloaded;
+/// web::scope("").wrap(RequirePreloaded);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RequirePreloaded;
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePreloaded
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Transform = RequirePreloadedMiddleware<S>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(RequirePreloadedMiddleware { service }))
+	}
+}
+
+/// service created by [RequirePreloaded]
+pub struct RequirePreloadedMiddleware<S> {
+	service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePreloadedMiddleware<S>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		if !is_preloaded() {
+			return Box::pin(ready(Ok(req.error_response(ApiError::Unavailable).map_into_right_body())));
+		}
+		let res = self.service.call(req);
+		Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) })
+	}
+}
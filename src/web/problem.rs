@@ -0,0 +1,339 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use actix_web::http::StatusCode;
+use actix_web::body::{BoxBody, EitherBody};
+use actix_web::error::PayloadError;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{Accept, CONTENT_TYPE, HeaderValue, Quality};
+use serde::{Deserialize, Serialize};
+
+use crate::web::error::{ApiError, FieldError};
+use crate::web::extract::PAYLOAD_LIMIT;
+use crate::web::request_id::RequestId;
+
+/// media type of [Problem]
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// shape of error response body
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorFormat {
+	/// `{"ok":false,"error":"...","code":"..."}` see [crate::web::error::ApiStatus]
+	Status,
+	/// RFC 7807 `application/problem+json` see [Problem]
+	Problem,
+}
+
+impl FromStr for ErrorFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"status" => Ok(ErrorFormat::Status),
+			"problem" => Ok(ErrorFormat::Problem),
+			_ => Err("expected `status` or `problem`".to_string()),
+		}
+	}
+}
+
+/// RFC 7807 problem details of [ApiError]
+/// ```json
+/// {"type":"about:blank","title":"Unauthorized","status":401,"detail":"Expired token!","instance":"/auth/logout","code":"expired_token","request_id":"..."}
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct Problem {
+	/// `HTTP_ERROR_TYPE_BASE` followed by [ApiError::code], or `about:blank` when it isn't set
+	#[serde(rename = "type")]
+	pub problem_type: String,
+	/// reason phrase of status like `Bad Request`
+	pub title: String,
+	/// http status code
+	pub status: u16,
+	/// same message as `error` of [crate::web::error::ApiStatus]
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub detail: Option<String>,
+	/// path of request
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub instance: Option<String>,
+	/// extension, same as [ApiError::code]
+	pub code: String,
+	/// extension, what part of request is invalid, see [ApiError::InvalidInput]
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub details: Vec<FieldError>,
+	/// extension, same as `X-Request-Id` response header
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<String>,
+}
+
+impl Problem {
+	/// problem of `err` raised while handling `req`
+	pub fn new(err: &ApiError, req: &HttpRequest, type_base: Option<&str>) -> Self {
+		Self::of_request(err, req.path(), req.extensions().get::<RequestId>().map(RequestId::to_string), type_base)
+	}
+
+	fn of_request(err: &ApiError, path: &str, request_id: Option<String>, type_base: Option<&str>) -> Self {
+		let status = err.status_code();
+		Problem {
+			problem_type: match type_base {
+				Some(base) => format!("{}{}", base, err.code()),
+				None => "about:blank".to_string(),
+			},
+			title: status.canonical_reason().unwrap_or("Unknown").to_string(),
+			status: status.as_u16(),
+			detail: Some(err.to_string()),
+			instance: Some(path.to_string()),
+			code: err.code().to_string(),
+			details: match err {
+				ApiError::InvalidInput(detail) => vec![detail.clone()],
+				_ => Vec::new(),
+			},
+			request_id,
+		}
+	}
+}
+
+/// format client asked for in `Accept` header, `application/problem+json` or `application/json`
+/// with higher quality win, `default` is used if neither is listed
+pub fn negotiate(req: &HttpRequest, default: ErrorFormat) -> ErrorFormat {
+	let accept = match req.get_header::<Accept>() {
+		Some(accept) => accept,
+		None => return default,
+	};
+	let mut best: Option<(Quality, ErrorFormat)> = None;
+	for item in accept.iter() {
+		let format = match item.item.essence_str() {
+			PROBLEM_JSON => ErrorFormat::Problem,
+			"application/json" => ErrorFormat::Status,
+			_ => continue,
+		};
+		// `q=0` means not acceptable
+		if item.quality > best.map_or(Quality::ZERO, |(quality, _)| quality) {
+			best = Some((item.quality, format));
+		}
+	}
+	best.map_or(default, |(_, format)| format)
+}
+
+/// middleware render [ApiError] as [Problem] when it's the default format (`HTTP_ERROR_FORMAT=problem`)
+/// or client send `Accept: application/problem+json`, other response is passed through
+///
+/// `Bytes` / `String` body over limit fail with actix `PayloadError` (plain text),
+/// it's replaced with [ApiError::PayloadTooLarge] in both format
+/// ```rust
+/// use actix_web::App;
+/// use actix_mongo_jwt_web_template::web::problem::{ErrorFormat, ProblemResponse};
+/// App::new().wrap(ProblemResponse::new(ErrorFormat::Status, None));
+/// ```
+#[derive(Clone)]
+pub struct ProblemResponse {
+	default: ErrorFormat,
+	type_base: Option<Rc<str>>,
+}
+
+impl ProblemResponse {
+	/// `type_base` is prefixed to [ApiError::code] as problem `type`, e.g. `https://example.com/errors/`
+	pub fn new(default: ErrorFormat, type_base: Option<&str>) -> Self {
+		Self { default, type_base: type_base.map(Rc::from) }
+	}
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ProblemResponse
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Transform = ProblemResponseMiddleware<S>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(ProblemResponseMiddleware { service, config: self.clone() }))
+	}
+}
+
+/// service created by [ProblemResponse]
+pub struct ProblemResponseMiddleware<S> {
+	service: S,
+	config: ProblemResponse,
+}
+
+impl<S, B> Service<ServiceRequest> for ProblemResponseMiddleware<S>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error>,
+	      S::Future: 'static,
+	      B: 'static {
+	type Response = ServiceResponse<EitherBody<B>>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		let format = negotiate(req.request(), self.config.default);
+		let type_base = self.config.type_base.clone();
+		// middleware like `RequireRoles` and extractor of it return error instead of response,
+		// request can't be cloned before routing so what problem need is taken now
+		let path = req.path().to_string();
+		let request_id = req.extensions().get::<RequestId>().map(RequestId::to_string);
+		let res = self.service.call(req);
+		Box::pin(async move {
+			let res = match res.await {
+				Ok(res) => res,
+				Err(err) => {
+					let err = payload_overflow(&err).map_or(err, Error::from);
+					if format == ErrorFormat::Status {
+						return Err(err);
+					}
+					let problem = match err.as_error::<ApiError>() {
+						Some(api_error) => Problem::of_request(api_error, &path, request_id, type_base.as_deref()),
+						None => return Err(err),
+					};
+					let body = serde_json::to_string(&problem)?;
+					return Err(ProblemError { source: err, body }.into());
+				}
+			};
+			let res = match res.response().error().and_then(payload_overflow) {
+				Some(err) => res.into_response(HttpResponse::from_error(err)).map_into_right_body(),
+				None => res.map_into_left_body(),
+			};
+			if format == ErrorFormat::Status {
+				return Ok(res);
+			}
+			let problem = match res.response().error().and_then(|it| it.as_error::<ApiError>()) {
+				Some(err) => Problem::new(err, res.request(), type_base.as_deref()),
+				None => return Ok(res),
+			};
+			let body = serde_json::to_string(&problem)?;
+			// status, header and error are kept for outer middleware
+			let mut res = res.map_body(|_, _| BoxBody::new(body));
+			res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+			Ok(res.map_into_right_body())
+		})
+	}
+}
+
+/// actix error of `Bytes` / `String` body over [PAYLOAD_LIMIT] as [ApiError]
+fn payload_overflow(err: &Error) -> Option<ApiError> {
+	match err.as_error::<PayloadError>() {
+		Some(PayloadError::Overflow) => Some(ApiError::PayloadTooLarge(PAYLOAD_LIMIT)),
+		_ => None,
+	}
+}
+
+/// [ApiError] returned as `Err` by inner service, rendered as [Problem] when actix build the response
+struct ProblemError {
+	source: Error,
+	body: String,
+}
+
+impl Debug for ProblemError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		Debug::fmt(&self.source, f)
+	}
+}
+
+impl Display for ProblemError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		Display::fmt(&self.source, f)
+	}
+}
+
+impl ResponseError for ProblemError {
+	fn status_code(&self) -> StatusCode {
+		self.source.as_response_error().status_code()
+	}
+
+	fn error_response(&self) -> HttpResponse {
+		// internal error is still logged by `ApiError`
+		let mut res = self.source.error_response().set_body(BoxBody::new(self.body.clone()));
+		res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+		res
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use actix_web::{App, web};
+	use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+	use actix_web::http::header::ACCEPT;
+
+	use super::*;
+
+	fn accept(value: &str, default: ErrorFormat) -> ErrorFormat {
+		negotiate(&TestRequest::default().insert_header((ACCEPT, value)).to_http_request(), default)
+	}
+
+	#[test]
+	fn negotiate_missing_header() {
+		let req = TestRequest::default().to_http_request();
+		assert_eq!(negotiate(&req, ErrorFormat::Status), ErrorFormat::Status);
+		assert_eq!(negotiate(&req, ErrorFormat::Problem), ErrorFormat::Problem);
+	}
+
+	#[test]
+	fn negotiate_media_type() {
+		assert_eq!(accept(PROBLEM_JSON, ErrorFormat::Status), ErrorFormat::Problem);
+		assert_eq!(accept("application/json", ErrorFormat::Problem), ErrorFormat::Status);
+		assert_eq!(accept("text/html", ErrorFormat::Problem), ErrorFormat::Problem);
+		// parameter other than quality doesn't matter
+		assert_eq!(accept("application/problem+json; charset=utf-8", ErrorFormat::Status), ErrorFormat::Problem);
+	}
+
+	#[test]
+	fn negotiate_quality() {
+		assert_eq!(accept("application/json;q=0.5, application/problem+json", ErrorFormat::Status), ErrorFormat::Problem);
+		assert_eq!(accept("application/json, application/problem+json;q=0.9", ErrorFormat::Problem), ErrorFormat::Status);
+		// same quality, first listed win
+		assert_eq!(accept("application/problem+json;q=0.8, application/json;q=0.8", ErrorFormat::Status), ErrorFormat::Problem);
+		// `q=0` isn't acceptable
+		assert_eq!(accept("application/problem+json;q=0", ErrorFormat::Problem), ErrorFormat::Problem);
+		assert_eq!(accept("application/json;q=0, application/problem+json;q=0.1", ErrorFormat::Status), ErrorFormat::Problem);
+		assert_eq!(accept("application/problem+json;q=0, application/json;q=0", ErrorFormat::Problem), ErrorFormat::Problem);
+	}
+
+	#[test]
+	fn negotiate_wildcard() {
+		assert_eq!(accept("*/*", ErrorFormat::Status), ErrorFormat::Status);
+		assert_eq!(accept("*/*", ErrorFormat::Problem), ErrorFormat::Problem);
+		assert_eq!(accept("application/*", ErrorFormat::Problem), ErrorFormat::Problem);
+		// explicit media type win over wildcard regardless of quality
+		assert_eq!(accept("*/*, application/problem+json;q=0.1", ErrorFormat::Status), ErrorFormat::Problem);
+		assert_eq!(accept("text/html, application/*;q=0.9, application/json;q=0.8", ErrorFormat::Problem), ErrorFormat::Status);
+	}
+
+	async fn echo(body: String) -> String {
+		body
+	}
+
+	#[actix_web::test]
+	async fn payload_overflow_in_both_format() {
+		let app = init_service(App::new()
+			.wrap(ProblemResponse::new(ErrorFormat::Status, None))
+			.app_data(crate::web::extract::payload_config())
+			.route("/echo", web::post().to(echo))).await;
+		let body = "a".repeat(PAYLOAD_LIMIT + 1);
+
+		let req = TestRequest::post().uri("/echo").set_payload(body.clone()).to_request();
+		let res = call_service(&app, req).await;
+		assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+		let status: serde_json::Value = read_body_json(res).await;
+		assert_eq!(status["code"], "payload_too_large");
+
+		let req = TestRequest::post().uri("/echo").insert_header((ACCEPT, PROBLEM_JSON)).set_payload(body).to_request();
+		let res = call_service(&app, req).await;
+		assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+		assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), PROBLEM_JSON);
+		let problem: Problem = read_body_json(res).await;
+		assert_eq!(problem.code, "payload_too_large");
+		assert_eq!(problem.instance.as_deref(), Some("/echo"));
+
+		let req = TestRequest::post().uri("/echo").set_payload("a".repeat(PAYLOAD_LIMIT)).to_request();
+		let res = call_service(&app, req).await;
+		assert_eq!(res.status(), StatusCode::OK);
+	}
+}
@@ -0,0 +1,146 @@
+use std::future::{Future, ready, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::{dev, Error, FromRequest, HttpMessage, HttpRequest};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use tracing::Instrument;
+use tracing::field::Empty;
+
+/// header used to receive / response request id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// longer request id from client is replaced
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// id of current request, taken from `X-Request-Id` header or generated
+///
+/// can be extracted in handler
+/// ```rust
+/// use actix_web::{get, Responder};
+/// use actix_mongo_jwt_web_template::web::request_id::RequestId;
+/// #[get("/")]
+/// async fn index(id: RequestId) -> impl Responder { id.to_string() }
+/// ```
+#[derive(Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+	/// use id from client if it's reasonable, otherwise generate new one
+	fn from_header(value: Option<&HeaderValue>) -> Self {
+		let value = value
+			.and_then(|it| it.to_str().ok())
+			.filter(|it| !it.is_empty() && it.len() <= REQUEST_ID_MAX_LEN)
+			.filter(|it| it.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')));
+		match value {
+			Some(value) => RequestId(value.to_string()),
+			None => RequestId(format!("{:032x}", rand::random::<u128>())),
+		}
+	}
+
+	/// get id as str
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl std::fmt::Display for RequestId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl FromRequest for RequestId {
+	type Error = Error;
+	type Future = Ready<Result<Self, Self::Error>>;
+
+	fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+		// middleware isn't attached, still give request an id
+		let id = req.extensions().get::<RequestId>().cloned()
+			.unwrap_or_else(|| RequestId::from_header(req.headers().get(REQUEST_ID_HEADER)));
+		ready(Ok(id))
+	}
+}
+
+/// middleware assign [RequestId] to request, echo it in `X-Request-Id` response header
+/// and open tracing span `request` with `request_id`, `method`, `route`, `status` and `user_id`
+/// (`user_id` is recorded when jwt is extracted)
+///
+/// with `otel` feature, span continue the trace from W3C `traceparent` request header
+/// ```rust
+/// use actix_web::App;
+/// use actix_mongo_jwt_web_template::web::request_id::RequestTracing;
+/// App::new().wrap(RequestTracing);
+/// ```
+#[derive(Clone, Copy)]
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Transform = RequestTracingMiddleware<S>;
+	type InitError = ();
+	type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		ready(Ok(RequestTracingMiddleware { service: Rc::new(service) }))
+	}
+}
+
+/// service created by [RequestTracing]
+pub struct RequestTracingMiddleware<S> {
+	service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingMiddleware<S>
+	where S: Service<ServiceRequest, Response=ServiceResponse<B>, Error=Error> + 'static,
+	      B: 'static {
+	type Response = ServiceResponse<B>;
+	type Error = Error;
+	type Future = Pin<Box<dyn Future<Output=Result<Self::Response, Self::Error>>>>;
+
+	forward_ready!(service);
+
+	fn call(&self, req: ServiceRequest) -> Self::Future {
+		let service = self.service.clone();
+		let id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+		let span = tracing::info_span!(
+			"request",
+			request_id = id.as_str(),
+			method = %req.method(),
+			route = Empty,
+			status = Empty,
+			user_id = Empty,
+		);
+		// continue trace of caller from W3C `traceparent` header
+		#[cfg(feature = "otel")]
+		{
+			use tracing_opentelemetry::OpenTelemetrySpanExt;
+			let _ = span.set_parent(crate::util::otel::extract_context(req.headers()));
+		}
+		req.extensions_mut().insert(id.clone());
+
+		Box::pin(async move {
+			let result = service.call(req).await;
+			let span = tracing::Span::current();
+			match result {
+				Ok(mut res) => {
+					span.record("route", res.request().match_pattern().as_deref().unwrap_or(res.request().path()));
+					span.record("status", res.status().as_u16());
+					if let Ok(value) = HeaderValue::from_str(id.as_str()) {
+						res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+					}
+					tracing::info!("request completed");
+					Ok(res)
+				}
+				Err(err) => {
+					span.record("status", err.as_response_error().status_code().as_u16());
+					tracing::info!("request failed: {}", err);
+					Err(err)
+				}
+			}
+		}.instrument(span))
+	}
+}